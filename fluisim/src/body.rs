@@ -0,0 +1,393 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::borrow::Cow;
+
+use hydrodynamics::*;
+use hydrodynamics::solver::*;
+use crate::settings::*;
+use crate::simulation::*;
+use crate::state::*;
+use crate::particle::*;
+
+/// A rigid body in the fluid, of the shape and size it was spawned with,
+/// which floats or sinks with the density of the settings.
+///
+/// The position and angle of the body are those of its transform. The rigid
+/// body the solver moves is built once and kept with the body, rather than
+/// sampled afresh by every system, and only placed where the body is.
+///
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub(crate) struct Body
+{
+    pub shape: BodyShape,
+    pub size: f32,
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+    #[serde(skip)]
+    cache: Option<RigidBodyCache>,
+}
+
+/// The rigid body of a body and its collider, only rebuilt when the settings
+/// they were built from change, with the collider only rebuilt when the body
+/// moves.
+///
+/// ## Fields
+///
+/// * `settings`   - The settings the rigid body was built from.
+/// * `rigid_body` - The rigid body the solver moves.
+/// * `collider`   - The collider of the rigid body.
+/// * `moved`      - Whether the rigid body has moved since its collider was
+///   built.
+///
+#[derive(Clone)]
+struct RigidBodyCache
+{
+    settings: Settings,
+    rigid_body: RigidBody,
+    collider: Collider<2>,
+    moved: bool,
+}
+
+impl Body
+{
+    /// Create a new body of a shape and size at rest.
+    ///
+    pub(crate) fn new(shape: BodyShape, size: f32) -> Self
+    {
+        Self {
+            shape,
+            size,
+            velocity: Vec2::ZERO,
+            angular_velocity: 0.0,
+            cache: None,
+        }
+    }
+
+    /// Bundle the body with a transform placing it at the given position and
+    /// angle, drawn in front of the particles.
+    ///
+    pub(crate) fn bundle(self, position: Vec2, angle: f32) -> impl Bundle
+    {
+        let transform = Transform::IDENTITY
+            .with_translation(position.extend(1.0))
+            .with_rotation(Quat::from_rotation_z(angle))
+            ;
+
+        (transform, self)
+    }
+
+    /// The vertices of the body about its centre, unrotated, in order around
+    /// it, if it is a polygon.
+    ///
+    fn vertices(&self) -> Option<Vec<Vec2>>
+    {
+        let size = self.size;
+
+        match self.shape
+        {
+            BodyShape::Square => Some(vec![
+                Vec2::new(-size, -size),
+                Vec2::new( size, -size),
+                Vec2::new( size,  size),
+                Vec2::new(-size,  size),
+            ]),
+            BodyShape::Triangle => Some((0..3)
+                .map(|corner|
+                {
+                    let angle = std::f32::consts::FRAC_PI_2 + std::f32::consts::TAU * corner as f32 / 3.0;
+                    Vec2::from_angle(angle) * size
+                })
+                .collect()),
+            BodyShape::None | BodyShape::Circle => None,
+        }
+    }
+
+    /// The mesh to draw the body with, if it has a shape.
+    ///
+    fn mesh(&self) -> Option<Mesh>
+    {
+        match self.shape
+        {
+            BodyShape::None => None,
+            BodyShape::Circle => Some(Circle::new(self.size).into()),
+            BodyShape::Square => Some(Rectangle::new(2.0 * self.size, 2.0 * self.size).into()),
+            BodyShape::Triangle =>
+            {
+                let vertices = self.vertices()?;
+                Some(Triangle2d::new(vertices[0], vertices[1], vertices[2]).into())
+            },
+        }
+    }
+
+    /// Build the rigid body of the shape of the body, at rest about the
+    /// origin, with its boundary particles spaced by the particle radius, if
+    /// it has a shape.
+    ///
+    fn build_rigid_body(&self, settings: &Settings) -> Option<RigidBody>
+    {
+        let density = settings.body_density() as f64;
+        let spacing = settings.particle_radius as f64;
+        let to_vector = |v: Vec2| nalgebra::Vector2::new(v.x as f64, v.y as f64);
+
+        match self.shape
+        {
+            BodyShape::None => None,
+            BodyShape::Circle => Some(RigidBody::circle(nalgebra::Vector2::zeros(), self.size as f64, density, spacing)),
+            BodyShape::Square | BodyShape::Triangle =>
+            {
+                let vertices = self.vertices()?.into_iter().map(to_vector).collect::<Vec<_>>();
+                Some(RigidBody::polygon(&vertices, density, spacing))
+            },
+        }
+    }
+
+    /// Rebuild the rigid body when the settings differ from those it was
+    /// built from, and its collider when the body has moved since the
+    /// collider was built, leaving both where the body is now.
+    ///
+    /// Settings are compared by value, as the ui borrows them mutably every
+    /// frame whether or not they change.
+    ///
+    pub(crate) fn update_cache(&mut self, transform: &Transform, settings: &Settings, kernel: &FieldKernel<2>)
+    {
+        if self.cache.as_ref().is_none_or(|cache| cache.settings != *settings)
+        {
+            self.cache = self.build_rigid_body(settings).map(|rigid_body|
+            {
+                RigidBodyCache {
+                    settings: *settings,
+                    collider: rigid_body.collider(kernel),
+                    rigid_body,
+                    moved: true,
+                }
+            });
+        }
+
+        self.place(transform);
+
+        let Some(cache) = self.cache.as_mut() else { return };
+
+        if cache.moved
+        {
+            cache.collider = cache.rigid_body.collider(kernel);
+            cache.moved = false;
+        }
+    }
+
+    /// Place the rigid body where the body is now and moving as it is, noting
+    /// whether it has moved.
+    ///
+    fn place(&mut self, transform: &Transform)
+    {
+        let to_vector = |v: Vec2| nalgebra::Vector2::new(v.x as f64, v.y as f64);
+
+        let position = to_vector(transform.translation.truncate());
+        let angle = transform.rotation.to_euler(EulerRot::ZYX).0 as f64;
+        let velocity = to_vector(self.velocity);
+        let angular_velocity = self.angular_velocity as f64;
+
+        let Some(cache) = self.cache.as_mut() else { return };
+        let rigid_body = &mut cache.rigid_body;
+
+        cache.moved |= rigid_body.position != position
+            || rigid_body.angle != angle
+            || rigid_body.velocity != velocity
+            || rigid_body.angular_velocity != angular_velocity;
+
+        rigid_body.position = position;
+        rigid_body.angle = angle;
+        rigid_body.velocity = velocity;
+        rigid_body.angular_velocity = angular_velocity;
+    }
+
+    /// The rigid body the solver moves, placed where the body is now and
+    /// moving as it is, if it has a shape and has been built.
+    ///
+    /// The collider of the body is rebuilt when the cache is next updated, as
+    /// the rigid body may be moved.
+    ///
+    pub(crate) fn rigid_body_mut(&mut self, transform: &Transform) -> Option<&mut RigidBody>
+    {
+        self.place(transform);
+
+        let cache = self.cache.as_mut()?;
+        cache.moved = true;
+
+        Some(&mut cache.rigid_body)
+    }
+
+    /// The collider of the body, where the body was when the cache was last
+    /// updated, if it has a shape and has been built.
+    ///
+    pub(crate) fn collider(&self) -> Option<&Collider<2>>
+    {
+        self.cache.as_ref().map(|cache| &cache.collider)
+    }
+
+    /// Take on the position, angle and velocities of the rigid body the solver
+    /// moved.
+    ///
+    pub(crate) fn follow(&mut self, transform: &mut Transform)
+    {
+        let Some(cache) = &self.cache else { return };
+        let rigid_body = &cache.rigid_body;
+
+        transform.translation.x = rigid_body.position.x as f32;
+        transform.translation.y = rigid_body.position.y as f32;
+        transform.rotation = Quat::from_rotation_z(rigid_body.angle as f32);
+
+        self.velocity = Vec2::new(rigid_body.velocity.x as f32, rigid_body.velocity.y as f32);
+        self.angular_velocity = rigid_body.angular_velocity as f32;
+    }
+}
+
+/// The solver with the collider of every body added after its own, so the
+/// fluid flows around the bodies where they are, borrowed unchanged when
+/// there are no bodies.
+///
+pub(crate) fn with_body_colliders<'a, 'b>(
+    solver: &'a Solver<2>,
+    bodies: impl Iterator<Item = &'b Body>,
+) -> Cow<'a, Solver<2>>
+{
+    let mut solver = Cow::Borrowed(solver);
+
+    for collider in bodies.filter_map(Body::collider)
+    {
+        solver.to_mut().colliders.push(collider.clone());
+    }
+
+    solver
+}
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+pub(crate) struct BodySystem;
+
+impl Plugin for BodySystem
+{
+    fn build(&self, app: &mut App)
+    {
+        let step = ||
+        (
+            BodySystem::movement,
+            BodySystem::confine_to_domain,
+        )
+        .chain()
+        .in_set(BodySystem)
+        .after(ParticleSystem)
+        .run_if(in_state(SimState::Running));
+
+        app.add_systems(Update, step()
+            .run_if(not(in_deterministic_mode))
+            );
+
+        app.add_systems(FixedUpdate, step()
+            .run_if(in_deterministic_mode)
+            );
+
+        // The rigid bodies are brought up to date before the particles feel
+        // them, and again once they have moved, so they are drawn against
+        // where the bodies are.
+        //
+        let update_rigid_bodies = || BodySystem::update_rigid_bodies
+            .after(Simulation::update_solver_cache);
+
+        app.add_systems(Update, (
+            update_rigid_bodies().before(ParticleSystem),
+            update_rigid_bodies().after(BodySystem),
+            ));
+
+        app.add_systems(FixedUpdate, (
+            update_rigid_bodies().before(ParticleSystem),
+            update_rigid_bodies().after(BodySystem),
+            ));
+    }
+}
+
+impl BodySystem
+{
+    /// Rebuild the rigid body of every body when the settings change, and
+    /// its collider when it has moved.
+    ///
+    fn update_rigid_bodies(
+        mut bodies: Query<(&Transform, &mut Body)>,
+        solver_cache: Res<SolverCache>,
+        settings: Res<Settings>,
+    ){
+        for (transform, mut body) in bodies.iter_mut()
+        {
+            body.update_cache(transform, &settings, &solver_cache.solver().kernel);
+        }
+    }
+
+    fn movement(
+        mut bodies: Query<(&mut Transform, &mut Body)>,
+        time: Res<Time>,
+    ){
+        for (mut transform, mut body) in bodies.iter_mut()
+        {
+            let Some(rigid_body) = body.rigid_body_mut(&transform) else { continue };
+
+            rigid_body.advance(time.delta_secs() as f64);
+            body.follow(&mut transform);
+        }
+    }
+
+    /// Keep bodies inside the domain, and out of the solids of the solver,
+    /// by their contact with the walls of the domain and the solids.
+    ///
+    fn confine_to_domain(
+        mut bodies: Query<(&mut Transform, &mut Body)>,
+        solver_cache: Res<SolverCache>,
+    ){
+        let colliders = solver_cache.solver().colliders.iter().chain([solver_cache.walls()]);
+
+        for (mut transform, mut body) in bodies.iter_mut()
+        {
+            let Some(rigid_body) = body.rigid_body_mut(&transform) else { continue };
+
+            for collider in colliders.clone()
+            {
+                rigid_body.collide(collider);
+            }
+
+            body.follow(&mut transform);
+        }
+    }
+}
+
+/// Draws every body as a grey shape.
+///
+/// Kept apart from the [`BodySystem`] so the simulation can also run without
+/// a window or a renderer.
+///
+pub(crate) struct BodyRenderer;
+
+impl Plugin for BodyRenderer
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(PostUpdate, BodyRenderer::on_body_spawned);
+    }
+}
+
+impl BodyRenderer
+{
+    fn on_body_spawned(
+        mut commands: Commands,
+        bodies: Query<(Entity, &Body), Added<Body>>,
+        mut meshes: ResMut<Assets<Mesh>>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+    ){
+        for (entity, body) in bodies.iter()
+        {
+            let Some(mesh) = body.mesh() else { continue };
+
+            let mesh = Mesh2d(meshes.add(mesh));
+            let material = MeshMaterial2d(materials.add(ColorMaterial::from(Color::srgb(0.6, 0.6, 0.6))));
+
+            commands.entity(entity).insert((mesh, material));
+        }
+    }
+}
@@ -0,0 +1,325 @@
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::path::{Path, PathBuf};
+
+use crate::body::*;
+use crate::settings::*;
+use crate::simulation::*;
+use crate::state::*;
+use crate::particle::*;
+
+pub(crate) struct CheckpointSystem;
+
+impl Plugin for CheckpointSystem
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<CheckpointFile>();
+        app.add_event::<CheckpointEvent>();
+
+        app.add_systems(Update, CheckpointSystem::on_checkpoint_event
+            .run_if(on_event::<CheckpointEvent>)
+            );
+    }
+}
+
+#[derive(Event, Clone, PartialEq)]
+pub(crate) enum CheckpointEvent
+{
+    Save(PathBuf),
+    Restore(PathBuf),
+}
+
+/// The checkpoint file last chosen in the ui.
+///
+#[derive(Resource, Clone, PartialEq)]
+pub(crate) struct CheckpointFile(pub String);
+
+impl Default for CheckpointFile
+{
+    fn default() -> Self
+    {
+        Self("checkpoint.ron".to_string())
+    }
+}
+
+/// A snapshot of the full simulation state, from which a run can be resumed
+/// exactly.
+///
+/// ## Fields
+///
+/// * `settings`  - The simulation settings.
+/// * `domain`    - The region the particles are confined to.
+/// * `time`      - The simulated time at the moment of capture.
+/// * `particles` - The position and attributes of every particle.
+/// * `bodies`    - The position, angle and motion of every rigid body.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Checkpoint
+{
+    pub settings: Settings,
+    pub domain: Domain,
+    pub time: SimTime,
+    pub particles: Vec<ParticleState>,
+    pub bodies: Vec<BodyState>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct ParticleState
+{
+    pub id: ParticleId,
+    pub position: Vec2,
+    pub particle: Particle,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct BodyState
+{
+    pub position: Vec2,
+    pub angle: f32,
+    pub body: Body,
+}
+
+#[derive(Debug)]
+pub(crate) enum CheckpointError
+{
+    Io(std::io::Error),
+    Format(String),
+}
+
+impl std::fmt::Display for CheckpointError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            CheckpointError::Io(error) => write!(f, "checkpoint io error: {}", error),
+            CheckpointError::Format(error) => write!(f, "checkpoint format error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl Checkpoint
+{
+    /// Capture the current state of the simulation.
+    ///
+    /// Particles are captured in id order, and bodies in order of position and
+    /// angle, so equal simulations always produce equal checkpoints.
+    ///
+    pub(crate) fn capture(world: &mut World) -> Self
+    {
+        let mut particles = world
+            .query::<(&ParticleId, &Transform, &Particle)>()
+            .iter(world)
+            .map(|(id, transform, particle)|
+            {
+                ParticleState {
+                    id: *id,
+                    position: transform.translation.truncate(),
+                    particle: particle.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        particles.sort_by_key(|state| state.id);
+
+        let mut bodies = world
+            .query::<(&Transform, &Body)>()
+            .iter(world)
+            .map(|(transform, body)|
+            {
+                BodyState {
+                    position: transform.translation.truncate(),
+                    angle: transform.rotation.to_euler(EulerRot::ZYX).0,
+                    body: body.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        bodies.sort_by(|a, b| a.position.x.total_cmp(&b.position.x)
+            .then(a.position.y.total_cmp(&b.position.y))
+            .then(a.angle.total_cmp(&b.angle)));
+
+        Self {
+            settings: *world.resource::<Settings>(),
+            domain: *world.resource::<Domain>(),
+            time: *world.resource::<SimTime>(),
+            particles,
+            bodies,
+        }
+    }
+
+    /// Replace the current state of the simulation with this checkpoint.
+    ///
+    /// The simulation is left paused, ready to be resumed. A checkpoint with a
+    /// particle in a phase the settings do not have is rejected, leaving the
+    /// simulation as it was.
+    ///
+    pub(crate) fn restore(self, world: &mut World) -> Result<(), CheckpointError>
+    {
+        let phases = self.settings.phases().len();
+        if let Some(state) = self.particles.iter().find(|state| state.particle.phase >= phases)
+        {
+            return Err(CheckpointError::Format(format!(
+                "particle {} is in phase {} of {}", state.id.0, state.particle.phase, phases)));
+        }
+
+        let particles = world
+            .query_filtered::<Entity, With<Particle>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+
+        for particle in particles
+        {
+            world.entity_mut(particle).despawn_recursive();
+        }
+
+        let bodies = world
+            .query_filtered::<Entity, With<Body>>()
+            .iter(world)
+            .collect::<Vec<_>>();
+
+        for body in bodies
+        {
+            world.entity_mut(body).despawn_recursive();
+        }
+
+        for ParticleState { id, position, particle } in self.particles
+        {
+            world.spawn(particle.bundle(id, position, &self.settings));
+        }
+
+        for BodyState { position, angle, body } in self.bodies
+        {
+            world.spawn(body.bundle(position, angle));
+        }
+
+        world.insert_resource(self.settings);
+        world.insert_resource(self.domain);
+        world.insert_resource(self.time);
+        world.resource_mut::<NextState<SimState>>().set(SimState::Paused);
+
+        Ok(())
+    }
+
+    /// Hash the simulated time, particle state and body state, bit for bit.
+    ///
+    /// Two runs of a deterministic simulation from the same scene and seed
+    /// have equal digests after the same number of steps.
+    ///
+    pub(crate) fn digest(&self) -> u64
+    {
+        // 64-bit FNV-1a, which unlike the std hashers is stable across
+        // platforms and releases.
+        //
+        let hash_word = |hash: u64, word: u64| word.to_le_bytes()
+            .into_iter()
+            .fold(hash, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001B3));
+
+        let words = self.particles.iter()
+            .flat_map(|state|
+            [
+                state.id.0 as u64,
+                state.position.x.to_bits() as u64,
+                state.position.y.to_bits() as u64,
+                state.particle.velocity.x.to_bits() as u64,
+                state.particle.velocity.y.to_bits() as u64,
+                state.particle.phase as u64,
+                state.particle.temperature.to_bits() as u64,
+                state.particle.density.to_bits() as u64,
+            ]);
+
+        let body_words = self.bodies.iter()
+            .flat_map(|state|
+            [
+                state.body.shape as u64,
+                state.body.size.to_bits() as u64,
+                state.position.x.to_bits() as u64,
+                state.position.y.to_bits() as u64,
+                state.angle.to_bits() as u64,
+                state.body.velocity.x.to_bits() as u64,
+                state.body.velocity.y.to_bits() as u64,
+                state.body.angular_velocity.to_bits() as u64,
+            ]);
+
+        std::iter::once(self.time.steps)
+            .chain(words)
+            .chain(body_words)
+            .fold(0xCBF29CE484222325, hash_word)
+    }
+
+    /// Read a checkpoint from a file.
+    ///
+    pub(crate) fn load(path: &Path) -> Result<Self, CheckpointError>
+    {
+        let text = std::fs::read_to_string(path).map_err(CheckpointError::Io)?;
+        ron::from_str(&text).map_err(|e| CheckpointError::Format(e.to_string()))
+    }
+
+    /// Write the checkpoint to a file.
+    ///
+    pub(crate) fn save(&self, path: &Path) -> Result<(), CheckpointError>
+    {
+        let config = ron::ser::PrettyConfig::default();
+        let text = ron::ser::to_string_pretty(self, config)
+            .map_err(|e| CheckpointError::Format(e.to_string()))?;
+        std::fs::write(path, text).map_err(CheckpointError::Io)
+    }
+}
+
+impl CheckpointSystem
+{
+    fn on_checkpoint_event(
+        world: &mut World,
+    ){
+        let events = world
+            .resource_mut::<Events<CheckpointEvent>>()
+            .drain()
+            .collect::<Vec<_>>();
+
+        for event in events
+        {
+            match event
+            {
+                CheckpointEvent::Save(path) =>
+                {
+                    if let Err(error) = Checkpoint::capture(world).save(&path)
+                    {
+                        error!("Failed to save {}: {}", path.display(), error);
+                    }
+                }
+                CheckpointEvent::Restore(path) =>
+                {
+                    if let Err(error) = Checkpoint::load(&path).and_then(|checkpoint| checkpoint.restore(world))
+                    {
+                        error!("Failed to restore {}: {}", path.display(), error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::headless::*;
+
+    #[test]
+    fn restore_rejects_unknown_phase()
+    {
+        let mut app = Headless::app(Settings::default());
+        let mut checkpoint = Checkpoint::capture(app.world_mut());
+        let particles = checkpoint.particles.len();
+
+        checkpoint.particles[0].particle.phase = 2;
+
+        assert!(checkpoint.restore(app.world_mut()).is_err());
+        assert_eq!(Checkpoint::capture(app.world_mut()).particles.len(), particles);
+    }
+}
@@ -0,0 +1,216 @@
+
+use bevy::prelude::*;
+
+use hydrodynamics::io::*;
+use hydrodynamics::io::vtk::*;
+use hydrodynamics::solver::*;
+
+use crate::settings::*;
+use crate::simulation::*;
+use crate::state::*;
+use crate::particle::*;
+
+pub(crate) struct ExportSystem;
+
+impl Plugin for ExportSystem
+{
+    fn build(&self, app: &mut App)
+    {
+        app.init_resource::<Exporter>();
+
+        app.add_systems(Update,
+            ExportSystem::record
+            .after(Simulation::tick)
+            .run_if(in_state(SimState::Running))
+            .run_if(not(in_deterministic_mode))
+            .run_if(is_recording)
+            );
+
+        app.add_systems(FixedUpdate,
+            ExportSystem::record
+            .after(Simulation::tick)
+            .run_if(in_state(SimState::Running))
+            .run_if(in_deterministic_mode)
+            .run_if(is_recording)
+            );
+    }
+}
+
+/// The file format the simulation is exported as.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum ExportFormat
+{
+    /// A VTK time series, for ParaView.
+    #[default] Vtk,
+    /// A csv file per step.
+    Csv,
+    /// A binary dump file per step.
+    Binary,
+}
+
+impl ExportFormat
+{
+    pub(crate) const ALL: [ExportFormat;3] = [ExportFormat::Vtk, ExportFormat::Csv, ExportFormat::Binary];
+
+    pub(crate) fn name(&self) -> &'static str
+    {
+        match self
+        {
+            ExportFormat::Vtk => "vtk",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Binary => "bin",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat
+{
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err>
+    {
+        ExportFormat::ALL.into_iter()
+            .find(|format| format.name() == name)
+            .ok_or(format!("unknown export format {}", name))
+    }
+}
+
+enum ExportSeries
+{
+    Vtk(VtkSeries),
+    Dump(DumpSeries),
+}
+
+/// Writes every simulation step to a series of files while recording.
+///
+/// ## Fields
+///
+/// * `directory` - The directory the series is written to.
+/// * `format`    - The file format of the series.
+/// * `series`    - The series being recorded, if any.
+///
+#[derive(Resource)]
+pub(crate) struct Exporter
+{
+    pub directory: String,
+    pub format: ExportFormat,
+    series: Option<ExportSeries>,
+}
+
+impl Default for Exporter
+{
+    fn default() -> Self
+    {
+        Self {
+            directory: "frames".to_string(),
+            format: ExportFormat::default(),
+            series: None,
+        }
+    }
+}
+
+impl Exporter
+{
+    /// Start recording a new series into the export directory.
+    ///
+    pub(crate) fn start(&mut self) -> std::io::Result<()>
+    {
+        let series = match self.format
+        {
+            ExportFormat::Vtk => ExportSeries::Vtk(
+                VtkSeries::create(&self.directory, "fluisim", VtkFormat::UnstructuredGrid)?),
+            ExportFormat::Csv => ExportSeries::Dump(
+                DumpSeries::create(&self.directory, "fluisim", DumpFormat::Csv)?),
+            ExportFormat::Binary => ExportSeries::Dump(
+                DumpSeries::create(&self.directory, "fluisim", DumpFormat::Binary)?),
+        };
+
+        self.series = Some(series);
+        Ok(())
+    }
+
+    /// Stop recording the series.
+    ///
+    pub(crate) fn stop(&mut self)
+    {
+        self.series = None;
+    }
+
+    pub(crate) fn is_recording(&self) -> bool
+    {
+        self.series.is_some()
+    }
+
+    /// Build a frame of the particles, in id order, with their velocity,
+    /// mass, density and pressure.
+    ///
+    pub(crate) fn frame(
+        particles: &[(ParticleId, Vec2, Particle)],
+        solver: &Solver<2>,
+        settings: &Settings,
+        time: f64,
+    ) -> Frame<2>
+    {
+        let ids = particles.iter()
+            .map(|(id, _position, _particle)| id.0 as f64)
+            .collect::<Vec<_>>();
+
+        let mut particle_set = particle_set(
+            particles.iter().map(|(_id, position, particle)| (*position, particle)),
+            settings);
+
+        solver.update_densities(&mut particle_set);
+
+        let pressures = solver.pressures(&particle_set);
+
+        particle_set.add_scalar("id", ids);
+        particle_set.add_scalar("pressure", pressures);
+
+        Frame::from_particles(time, &particle_set)
+    }
+}
+
+/// Run condition for systems that only run while a time series is recorded.
+///
+pub(crate) fn is_recording(
+    exporter: Res<Exporter>,
+) -> bool
+{
+    exporter.is_recording()
+}
+
+impl ExportSystem
+{
+    fn record(
+        particles: Query<(&ParticleId, &Transform, &Particle)>,
+        mut exporter: ResMut<Exporter>,
+        solver_cache: Res<SolverCache>,
+        settings: Res<Settings>,
+        sim_time: Res<SimTime>,
+    ){
+        let mut particles = particles.iter()
+            .map(|(id, transform, particle)|
+            {
+                (*id, transform.translation.truncate(), particle.clone())
+            })
+            .collect::<Vec<_>>();
+
+        particles.sort_by_key(|(id, _position, _particle)| *id);
+
+        let frame = Exporter::frame(&particles, solver_cache.solver(), &settings, sim_time.elapsed);
+
+        let result = match &mut exporter.series
+        {
+            Some(ExportSeries::Vtk(series)) => series.write(&frame).map(|_| ()),
+            Some(ExportSeries::Dump(series)) => series.write(&frame).map(|_| ()),
+            None => Ok(()),
+        };
+
+        if let Err(error) = result
+        {
+            error!("Failed to export to {}: {}", exporter.directory, error);
+            exporter.stop();
+        }
+    }
+}
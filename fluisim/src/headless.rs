@@ -0,0 +1,256 @@
+
+use bevy::prelude::*;
+use bevy::log::LogPlugin;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::body::*;
+use crate::checkpoint::*;
+use crate::export::*;
+use crate::settings::*;
+use crate::simulation::*;
+use crate::state::*;
+use crate::particle::*;
+
+/// Runs the simulation without a window for a fixed number of steps.
+///
+/// The simulation always runs in deterministic mode, so the same checkpoint
+/// and step count always reproduce the same particles.
+///
+/// ## Fields
+///
+/// * `restore` - The checkpoint to resume from, else the default grid.
+/// * `save`    - The checkpoint to write once all steps have been taken.
+/// * `export`  - The directory to export every step to, and in which format.
+/// * `steps`   - The number of simulation steps to take.
+/// * `expect`  - The digest the final state must have, for regression checks.
+///
+pub(crate) struct Headless
+{
+    pub restore: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub export: Option<(String, ExportFormat)>,
+    pub steps: u64,
+    pub expect: Option<u64>,
+}
+
+impl Headless
+{
+    pub(crate) const USAGE: &str =
+        "usage: fluisim --headless [--restore <file>] [--save <file>] [--export <dir> [--export-format vtk|csv|bin]] [--steps <n>] [--expect-digest <hex>]";
+
+    /// Parse the headless options from the command line arguments, returning
+    /// `None` when the `--headless` flag is absent.
+    ///
+    pub(crate) fn from_args(args: &[String]) -> Option<Result<Self, String>>
+    {
+        if !args.iter().any(|arg| arg == "--headless") { return None };
+
+        Some(Self::parse(args))
+    }
+
+    fn parse(args: &[String]) -> Result<Self, String>
+    {
+        let mut headless = Self {
+            restore: None,
+            save: None,
+            export: None,
+            steps: 0,
+            expect: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next()
+        {
+            let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+
+            match arg.as_str()
+            {
+                "--headless" => {},
+                "--restore" => headless.restore = Some(PathBuf::from(value()?)),
+                "--save" => headless.save = Some(PathBuf::from(value()?)),
+                "--export" => headless.export = Some(( value()?.clone(), ExportFormat::default() )),
+                "--export-format" => match &mut headless.export
+                {
+                    Some(( _, format )) => *format = value()?.parse()?,
+                    None => return Err("--export-format requires --export".to_string()),
+                },
+                "--steps" => headless.steps = value()?.parse()
+                    .map_err(|e| format!("invalid --steps: {}", e))?,
+                "--expect-digest" => headless.expect = u64::from_str_radix(value()?, 16)
+                    .map(Some)
+                    .map_err(|e| format!("invalid --expect-digest: {}", e))?,
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+
+        Ok(headless)
+    }
+
+    /// Build the app the simulation runs in without a window, with the scene
+    /// spawned from the given settings.
+    ///
+    pub(crate) fn app(settings: Settings) -> App
+    {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(LogPlugin::default())
+            .add_plugins(StatesPlugin)
+            .add_plugins(ParticleSystem)
+            .add_plugins(BodySystem)
+            .add_plugins(SettingsSystem)
+            .add_plugins(Simulation)
+            .add_plugins(ExportSystem)
+            .insert_resource(settings);
+
+        app.finish();
+        app.cleanup();
+        app.update();
+
+        app
+    }
+
+    /// Run the simulation of an app in deterministic mode for a number of
+    /// steps, exactly one fixed timestep per update.
+    ///
+    pub(crate) fn step(app: &mut App, steps: u64)
+    {
+        let mut settings = app.world_mut().resource_mut::<Settings>();
+        settings.deterministic = true;
+        let timestep = Duration::from_secs_f64(settings.timestep as f64);
+
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+        app.world_mut().resource_mut::<NextState<SimState>>().set(SimState::Running);
+
+        let target = app.world().resource::<SimTime>().steps + steps;
+        while app.world().resource::<SimTime>().steps < target
+        {
+            app.update();
+        }
+    }
+
+    pub(crate) fn run(self) -> Result<(), HeadlessError>
+    {
+        let mut app = Headless::app(Settings::default());
+
+        if let Some(path) = &self.restore
+        {
+            Checkpoint::load(path)?.restore(app.world_mut())?;
+        }
+
+        if let Some(( directory, format )) = &self.export
+        {
+            let mut exporter = app.world_mut().resource_mut::<Exporter>();
+            exporter.directory = directory.clone();
+            exporter.format = *format;
+            exporter.start()?;
+        }
+
+        Headless::step(&mut app, self.steps);
+
+        let checkpoint = Checkpoint::capture(app.world_mut());
+        let digest = checkpoint.digest();
+
+        info!("Stopped after {} steps at t = {}s with digest {:016x}",
+            checkpoint.time.steps, checkpoint.time.elapsed, digest);
+
+        if let Some(path) = &self.save
+        {
+            checkpoint.save(path)?;
+        }
+
+        match self.expect
+        {
+            Some(expect) if expect != digest => Err(HeadlessError::Digest { expect, digest }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum HeadlessError
+{
+    Checkpoint(CheckpointError),
+    Export(std::io::Error),
+    Digest { expect: u64, digest: u64 },
+}
+
+impl From<CheckpointError> for HeadlessError
+{
+    fn from(error: CheckpointError) -> Self
+    {
+        HeadlessError::Checkpoint(error)
+    }
+}
+
+impl From<std::io::Error> for HeadlessError
+{
+    fn from(error: std::io::Error) -> Self
+    {
+        HeadlessError::Export(error)
+    }
+}
+
+impl std::fmt::Display for HeadlessError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            HeadlessError::Checkpoint(error) => write!(f, "{}", error),
+            HeadlessError::Export(error) => write!(f, "export error: {}", error),
+            HeadlessError::Digest { expect, digest } =>
+                write!(f, "digest mismatch: expected {:016x}, found {:016x}", expect, digest),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use bevy::math::U16Vec2;
+
+    /// A small two phase scene with jitter, heating and a floating body, so
+    /// the digest covers every part of the state.
+    ///
+    fn settings() -> Settings
+    {
+        Settings {
+            particle_count: U16Vec2::new(12, 8),
+            particle_radius: 10.0,
+            seed: 42,
+            jitter: 0.2,
+            two_phases: true,
+            heating: 10.0,
+            body_shape: BodyShape::Circle,
+            body_size: 30.0,
+            ..Settings::default()
+        }
+    }
+
+    fn digest(steps: u64) -> u64
+    {
+        let mut app = Headless::app(settings());
+        Headless::step(&mut app, steps);
+
+        Checkpoint::capture(app.world_mut()).digest()
+    }
+
+    #[test]
+    fn deterministic_runs_have_equal_digests()
+    {
+        let first = digest(20);
+        let second = digest(20);
+
+        assert_eq!(first, second);
+        assert_ne!(first, digest(0));
+        assert_eq!(first, 0x5F23582AAF244711, "digest {:016x}", first);
+    }
+}
@@ -8,22 +8,58 @@ mod settings;
 mod state;
 mod simulation;
 mod particle;
+mod body;
+mod checkpoint;
+mod export;
+mod headless;
 
 use ui::*;
 use settings::*;
 use simulation::*;
 use particle::*;
+use body::*;
+use checkpoint::*;
+use export::*;
+use headless::*;
 
-fn main()
+fn main() -> std::process::ExitCode
 {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match Headless::from_args(&args)
+    {
+        Some(Ok(headless)) => return match headless.run()
+        {
+            Ok(()) => std::process::ExitCode::SUCCESS,
+            Err(error) =>
+            {
+                eprintln!("{}", error);
+                std::process::ExitCode::FAILURE
+            }
+        },
+        Some(Err(error)) =>
+        {
+            eprintln!("{}\n{}", error, Headless::USAGE);
+            return std::process::ExitCode::FAILURE;
+        }
+        None => {}
+    }
+
     App::new()
         .add_plugins(DefaultPlugins)
         .add_systems(Startup, setup_camera)
         .add_plugins(ParticleSystem)
+        .add_plugins(ParticleRenderer)
+        .add_plugins(BodySystem)
+        .add_plugins(BodyRenderer)
         .add_plugins(UiSystem)
         .add_plugins(SettingsSystem)
         .add_plugins(Simulation)
+        .add_plugins(CheckpointSystem)
+        .add_plugins(ExportSystem)
         .run();
+
+    std::process::ExitCode::SUCCESS
 }
 
 fn setup_camera(
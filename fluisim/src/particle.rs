@@ -1,22 +1,91 @@
 
 use bevy::prelude::*;
-use bevy::window::*;
+use serde::{Deserialize, Serialize};
 
+use hydrodynamics::*;
 use util::*;
+use crate::body::*;
 use crate::settings::*;
+use crate::simulation::*;
 use crate::state::*;
 
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub(crate) struct Particle
 {
     pub velocity: Vec2,
+    pub phase: usize,
+    pub temperature: f32,
+    pub density: f32,
 }
 
+/// A stable identifier for a particle, which survives checkpoints.
+///
+/// Query iteration order is unspecified, so any system combining the state of
+/// several particles must visit them in id order for the result to be
+/// reproducible.
+///
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub(crate) struct ParticleId(pub u32);
+
+impl Particle
+{
+    /// Bundle the particle with its id and a transform placing it at the given
+    /// position.
+    ///
+    pub(crate) fn bundle(self, id: ParticleId, position: Vec2, settings: &Settings) -> impl Bundle
+    {
+        let transform = Transform::IDENTITY
+            .with_scale(settings.particle_scale())
+            .with_translation(position.extend(0.0))
+            ;
+
+        (transform, id, self)
+    }
+}
+
+/// Gather the position, velocity, phase, temperature and density of
+/// particles, already in id order, into a particle set with the mass of their
+/// phase.
+///
+pub(crate) fn particle_set<'a>(
+    particles: impl Iterator<Item = (Vec2, &'a Particle)>,
+    settings: &Settings,
+) -> ParticleSet<2>
+{
+    let to_vector = |v: Vec2| nalgebra::Vector2::new(v.x, v.y);
+
+    let mut particle_set = ParticleSet::new();
+    let mut phases = Vec::new();
+    let mut temperatures = Vec::new();
+    let mut densities = Vec::new();
+
+    for (position, particle) in particles
+    {
+        let mass = settings.phase_mass(particle.phase) as f64;
+        particle_set.push(to_vector(position), to_vector(particle.velocity), mass);
+        phases.push(particle.phase);
+        temperatures.push(particle.temperature as f64);
+        densities.push(particle.density as f64);
+    }
+
+    particle_set.phases = phases;
+    particle_set.temperatures = temperatures;
+    particle_set.densities = densities;
+    particle_set
+}
+
+/// The number of shades particles are drawn in when showing their
+/// temperature.
+///
+const TEMPERATURE_SHADES: usize = 16;
+
 #[derive(Resource, Clone, PartialEq)]
 pub(crate) struct ParticleResources
 {
     pub mesh: Handle<Mesh>,
-    pub material: Handle<ColorMaterial>,
+    pub materials: Vec<Handle<ColorMaterial>>,
+    pub temperature_materials: Vec<Handle<ColorMaterial>>,
+    pub free_surface_material: Handle<ColorMaterial>,
 }
 
 impl ParticleResources
@@ -25,17 +94,62 @@ impl ParticleResources
         mut commands: Commands,
         mut meshes: ResMut<Assets<Mesh>>,
         mut materials: ResMut<Assets<ColorMaterial>>,
+        settings: Res<Settings>,
     ){
         let particle_radius = *Settings::PARTICLE_RADIUS.upper_value().unwrap();
         let particle_mesh: Mesh = Circle::new(particle_radius).into();
-        let particle_material = ColorMaterial::from(Color::hsl(180.0, 1.0, 0.5));
+
+        // A material of the colour of every phase.
+        //
+        let particle_materials = settings.phases().iter()
+            .map(|phase|
+            {
+                let [red, green, blue] = phase.colour;
+                materials.add(ColorMaterial::from(Color::srgb(red, green, blue)))
+            })
+            .collect();
+
+        // A material for every shade from cold blue to hot red.
+        //
+        let temperature_materials = (0..TEMPERATURE_SHADES)
+            .map(|shade|
+            {
+                let heat = shade as f32 / (TEMPERATURE_SHADES - 1) as f32;
+                materials.add(ColorMaterial::from(Color::srgb(heat, 0.2, 1.0 - heat)))
+            })
+            .collect();
 
         commands.insert_resource(ParticleResources
         {
             mesh: meshes.add(particle_mesh),
-            material: materials.add(particle_material),
+            materials: particle_materials,
+            temperature_materials,
+            free_surface_material: materials.add(ColorMaterial::from(Color::WHITE)),
         });
     }
+
+    /// The material to draw a particle in: white when it is shown on the free
+    /// surface, the shade of its temperature between the floor and ceiling
+    /// temperatures when showing temperature while heating, and the colour of
+    /// its phase otherwise.
+    ///
+    fn material(&self, particle: &Particle, free_surface: bool, settings: &Settings) -> Handle<ColorMaterial>
+    {
+        if free_surface
+        {
+            self.free_surface_material.clone()
+        }
+        else if settings.show_temperature && settings.heating > 0.0
+        {
+            let heat = (particle.temperature / settings.heating + 1.0) / 2.0;
+            let shade = (heat * (TEMPERATURE_SHADES - 1) as f32).round().clamp(0.0, (TEMPERATURE_SHADES - 1) as f32);
+            self.temperature_materials[shade as usize].clone()
+        }
+        else
+        {
+            self.materials[particle.phase].clone()
+        }
+    }
 }
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
@@ -45,23 +159,29 @@ impl Plugin for ParticleSystem
 {
     fn build(&self, app: &mut App)
     {
-        app.add_systems(Startup, ParticleResources::setup
-            .in_set(ParticleSystem)
-            );
-
         app.add_systems(Update, ParticleSystem::on_particle_radius_changed
             .in_set(ParticleSystem)
             .run_if(on_event::<SettingsChangedEvent>)
             );
 
-        app.add_systems(Update,
-            (
-                ParticleSystem::on_gravity,
-                ParticleSystem::movement,
-                ParticleSystem::confine_to_window,
-            )
-            .chain()
-            .run_if(in_state(SimState::Running))
+        let step = ||
+        (
+            ParticleSystem::on_gravity,
+            ParticleSystem::on_fluid_forces,
+            ParticleSystem::movement,
+            ParticleSystem::on_shifting,
+            ParticleSystem::confine_to_domain,
+        )
+        .chain()
+        .in_set(ParticleSystem)
+        .run_if(in_state(SimState::Running));
+
+        app.add_systems(Update, step()
+            .run_if(not(in_deterministic_mode))
+            );
+
+        app.add_systems(FixedUpdate, step()
+            .run_if(in_deterministic_mode)
             );
     }
 }
@@ -95,17 +215,50 @@ impl ParticleSystem
         }
     }
 
-    fn confine_to_window(
-        mut particles: Query<(&mut Transform, &mut Particle)>,
-        window_query: Query<&Window, With<PrimaryWindow>>,
+    /// Shift particles towards an even spacing after they move, keeping the
+    /// density of every particle where it is shifted to.
+    ///
+    fn on_shifting(
+        mut particles: Query<(&ParticleId, &mut Transform, &mut Particle), Without<Body>>,
+        bodies: Query<&Body, Without<Particle>>,
+        solver_cache: Res<SolverCache>,
         settings: Res<Settings>,
+        time: Res<Time>
     ){
-        let window = window_query.get_single().unwrap();
+        if settings.shifting == 0.0 { return };
+
+        let mut particles = particles.iter_mut().collect::<Vec<_>>();
+        particles.sort_by_key(|(id, _transform, _particle)| **id);
+
+        let mut particle_set = particle_set(
+            particles.iter().map(|(_id, transform, particle)|
+            {
+                (transform.translation.truncate(), &**particle)
+            }),
+            &settings);
+
+        let solver = with_body_colliders(solver_cache.solver(), bodies.iter());
+        solver.shift_particles(&mut particle_set, time.delta_secs() as f64);
 
-        let bounds_min_x = -window.width()  / 2.0 + settings.particle_radius;
-        let bounds_max_x =  window.width()  / 2.0 - settings.particle_radius;
-        let bounds_min_y = -window.height() / 2.0 + settings.particle_radius;
-        let bounds_max_y =  window.height() / 2.0 - settings.particle_radius;
+        for ((_id, transform, particle), position, density) in itertools::izip!(
+            particles.iter_mut(),
+            &particle_set.positions,
+            &particle_set.densities)
+        {
+            transform.translation = Vec3::new(position.x, position.y, 0.0);
+            particle.density = *density as f32;
+        }
+    }
+
+    fn confine_to_domain(
+        mut particles: Query<(&mut Transform, &mut Particle)>,
+        domain: Res<Domain>,
+        settings: Res<Settings>,
+    ){
+        let bounds_min_x = -domain.size.x / 2.0 + settings.particle_radius;
+        let bounds_max_x =  domain.size.x / 2.0 - settings.particle_radius;
+        let bounds_min_y = -domain.size.y / 2.0 + settings.particle_radius;
+        let bounds_max_y =  domain.size.y / 2.0 - settings.particle_radius;
 
         for (mut transformation, mut particle) in particles.iter_mut()
         {
@@ -123,7 +276,7 @@ impl ParticleSystem
                 particle.velocity.y *= -1.0 * (1.0 - settings.border_damping);
             }
 
-            // clamp the particles position inside the window
+            // clamp the particles position inside the domain
             transformation.translation = transformation.translation.clamp(
                 Vec3::new(bounds_min_x, bounds_min_y, 0.0),
                 Vec3::new(bounds_max_x, bounds_max_y, 0.0),
@@ -144,4 +297,181 @@ impl ParticleSystem
             particle.velocity += gravity_vector * time.delta_secs();
         }
     }
+
+    /// Accelerate particles by the forces within the fluid, and bodies by the
+    /// forces of the fluid on them and gravity.
+    ///
+    fn on_fluid_forces(
+        mut particles: Query<(&ParticleId, &Transform, &mut Particle)>,
+        mut bodies: Query<(&Transform, &mut Body)>,
+        solver_cache: Res<SolverCache>,
+        settings: Res<Settings>,
+        time: Res<Time>
+    ){
+        let mut particles = particles.iter_mut().collect::<Vec<_>>();
+        particles.sort_by_key(|(id, _transform, _particle)| **id);
+
+        let mut particle_set = particle_set(
+            particles.iter().map(|(_id, transform, particle)|
+            {
+                (transform.translation.truncate(), &**particle)
+            }),
+            &settings);
+
+        let mut bodies = bodies.iter_mut()
+            .filter(|(_transform, body)| body.collider().is_some())
+            .collect::<Vec<_>>();
+
+        // The fluid flows around the colliders of the bodies after those of
+        // the solver.
+        //
+        let collider_count = solver_cache.solver().colliders.len();
+        let solver = with_body_colliders(solver_cache.solver(), bodies.iter().map(|(_transform, body)| &**body));
+
+        solver.update_densities(&mut particle_set);
+
+        let accelerations = solver.accelerations(&particle_set);
+        let heating_rates = solver.heating_rates(&particle_set);
+        let collider_forces = solver.collider_forces(&particle_set);
+
+        for (velocity, acceleration) in itertools::izip!(&mut particle_set.velocities, accelerations)
+        {
+            *velocity += acceleration.map(|v| v as f32) * settings.force_multiplier * time.delta_secs();
+        }
+
+        // Keep the density of every particle, which under continuity density
+        // evolves from step to step rather than being summed afresh, from its
+        // new velocity as in the semi-implicit step of the solver.
+        //
+        solver.integrate_densities(&mut particle_set, time.delta_secs() as f64);
+
+        for ((_id, _transform, particle), velocity, heating_rate, density) in itertools::izip!(
+            particles.iter_mut(),
+            &particle_set.velocities,
+            heating_rates,
+            &particle_set.densities)
+        {
+            particle.velocity = Vec2::new(velocity.x, velocity.y);
+            particle.temperature += heating_rate as f32 * time.delta_secs();
+            particle.density = *density as f32;
+        }
+
+        // Bodies are pushed by the fluid and pulled by gravity as hard as the
+        // particles are.
+        //
+        let force_multiplier = settings.force_multiplier as f64;
+        let gravity = nalgebra::Vector2::new(0.0, -settings.gravity as f64 * force_multiplier);
+
+        for ((transform, body), forces) in itertools::izip!(&mut bodies, &collider_forces[collider_count..])
+        {
+            let Some(rigid_body) = body.rigid_body_mut(transform) else { continue };
+
+            let forces = forces.iter()
+                .map(|force| force * force_multiplier)
+                .collect::<Vec<_>>();
+
+            rigid_body.accelerate(&forces, gravity, time.delta_secs() as f64);
+
+            let velocity = rigid_body.velocity;
+            let angular_velocity = rigid_body.angular_velocity;
+            body.velocity = Vec2::new(velocity.x as f32, velocity.y as f32);
+            body.angular_velocity = angular_velocity as f32;
+        }
+    }
+}
+
+/// Draws every particle as a coloured circle.
+///
+/// Kept apart from the [`ParticleSystem`] so the simulation can also run
+/// without a window or a renderer.
+///
+pub(crate) struct ParticleRenderer;
+
+impl Plugin for ParticleRenderer
+{
+    fn build(&self, app: &mut App)
+    {
+        app.add_systems(Startup, ParticleResources::setup
+            .in_set(ParticleSystem)
+            );
+
+        app.add_systems(PostUpdate, (
+            ParticleRenderer::on_particle_spawned,
+            ParticleRenderer::update_materials,
+            ));
+    }
+}
+
+impl ParticleRenderer
+{
+    fn on_particle_spawned(
+        mut commands: Commands,
+        particles: Query<(Entity, &Particle), Added<Particle>>,
+        particle_resources: Res<ParticleResources>,
+        settings: Res<Settings>,
+    ){
+        for (entity, particle) in particles.iter()
+        {
+            let mesh = Mesh2d(particle_resources.mesh.clone());
+            let material = MeshMaterial2d(particle_resources.material(particle, false, &settings));
+
+            commands.entity(entity).insert((mesh, material));
+        }
+    }
+
+    /// Redraw particles whose material no longer matches their phase,
+    /// temperature or place on the free surface, such as while fluid
+    /// convects, and draw the outward normal of every particle on the free
+    /// surface when showing it.
+    ///
+    fn update_materials(
+        mut particles: Query<(&ParticleId, &Transform, &Particle, &mut MeshMaterial2d<ColorMaterial>)>,
+        bodies: Query<&Body>,
+        particle_resources: Res<ParticleResources>,
+        solver_cache: Res<SolverCache>,
+        settings: Res<Settings>,
+        mut gizmos: Gizmos,
+    ){
+        let mut particles = particles.iter_mut().collect::<Vec<_>>();
+        particles.sort_by_key(|(id, _transform, _particle, _material)| **id);
+
+        // The normal of every particle on the free surface, only found while
+        // showing it.
+        //
+        let free_surface_normals = match settings.show_free_surface
+        {
+            true =>
+            {
+                let particle_set = particle_set(
+                    particles.iter().map(|(_id, transform, particle, _material)|
+                    {
+                        (transform.translation.truncate(), *particle)
+                    }),
+                    &settings);
+
+                with_body_colliders(solver_cache.solver(), bodies.iter())
+                    .free_surface_normals(&particle_set)
+            },
+            false => vec![None; particles.len()],
+        };
+
+        for ((_id, transform, particle, material), normal) in itertools::izip!(
+            particles.iter_mut(),
+            free_surface_normals)
+        {
+            let particle_material = particle_resources.material(particle, normal.is_some(), &settings);
+
+            if material.0 != particle_material
+            {
+                material.0 = particle_material;
+            }
+
+            if let Some(normal) = normal
+            {
+                let position = transform.translation.truncate();
+                let normal = Vec2::new(normal.x as f32, normal.y as f32);
+                gizmos.line_2d(position, position + normal * 2.0 * settings.particle_radius, Color::srgb(1.0, 0.2, 0.2));
+            }
+        }
+    }
 }
@@ -1,9 +1,15 @@
 
 use bevy::{math::U16Vec2, prelude::*};
+use serde::{Deserialize, Serialize};
 
+use hydrodynamics::*;
+use hydrodynamics::kernels::*;
+use hydrodynamics::solver::*;
 use util::*;
 use std::ops::RangeInclusive;
 
+use crate::simulation::*;
+
 pub(crate) struct SettingsSystem;
 
 impl Plugin for SettingsSystem
@@ -15,7 +21,7 @@ impl Plugin for SettingsSystem
     }
 }
 
-#[derive(Resource, Copy, Clone, PartialEq)]
+#[derive(Resource, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct Settings
 {
     pub particle_count: U16Vec2,
@@ -24,6 +30,34 @@ pub(crate) struct Settings
     pub border_damping: f32,
     pub gravity: f32,
     pub force_multiplier: f32,
+    pub deterministic: bool,
+    pub timestep: f32,
+    pub seed: u64,
+    pub jitter: f32,
+    pub smoothing_radius: f32,
+    pub target_density: f32,
+    pub pressure_multiplier: f32,
+    pub surface_tension: f32,
+    pub surface_tension_model: SurfaceTensionModel,
+    pub viscosity: f32,
+    pub rheologies: [PhaseRheology;2],
+    pub viscosity_alpha: f32,
+    pub viscosity_beta: f32,
+    pub balsara_switch: bool,
+    pub continuity_density: bool,
+    pub density_diffusion_model: DensityDiffusionModel,
+    pub density_diffusion: f32,
+    pub shifting: f32,
+    pub two_phases: bool,
+    pub phase_density_ratio: f32,
+    pub heating: f32,
+    pub diffusivity: f32,
+    pub expansion: f32,
+    pub show_temperature: bool,
+    pub show_free_surface: bool,
+    pub body_shape: BodyShape,
+    pub body_size: f32,
+    pub body_density_ratio: f32,
 }
 
 impl Settings
@@ -35,6 +69,26 @@ impl Settings
     pub(crate) const BORDER_DAMPING:      RangeInclusive<f32> = 0.0 ..=    1.0;
     pub(crate) const GRAVITY:             RangeInclusive<f32> = 0.0 ..=   20.0;
     pub(crate) const FORCE_MULTIPLIER:    RangeInclusive<f32> = 0.0 ..=  100.0;
+    pub(crate) const TIMESTEP:            RangeInclusive<f32> = 0.001 ..=    0.1;
+    pub(crate) const JITTER:              RangeInclusive<f32> = 0.0 ..=    0.5;
+    pub(crate) const SMOOTHING_RADIUS:    RangeInclusive<f32> = 1.0 ..=  500.0;
+    pub(crate) const TARGET_DENSITY:      RangeInclusive<f32> = 0.1 ..=   10.0;
+    pub(crate) const PRESSURE_MULTIPLIER: RangeInclusive<f32> = 0.0 ..= 1000.0;
+    pub(crate) const SURFACE_TENSION:     RangeInclusive<f32> = 0.0 ..= 10000.0;
+    pub(crate) const VISCOSITY:           RangeInclusive<f32> = 0.0 ..= 1000.0;
+    pub(crate) const FLOW_INDEX:          RangeInclusive<f32> = 0.1 ..=    2.0;
+    pub(crate) const TIME_CONSTANT:       RangeInclusive<f32> = 0.001 ..= 10.0;
+    pub(crate) const YIELD_STRESS:        RangeInclusive<f32> = 0.0 ..= 1000.0;
+    pub(crate) const VISCOSITY_ALPHA:     RangeInclusive<f32> = 0.0 ..=    1.0;
+    pub(crate) const VISCOSITY_BETA:      RangeInclusive<f32> = 0.0 ..=    2.0;
+    pub(crate) const DENSITY_DIFFUSION:   RangeInclusive<f32> = 0.0 ..=    1.0;
+    pub(crate) const SHIFTING:            RangeInclusive<f32> = 0.0 ..=   10.0;
+    pub(crate) const PHASE_DENSITY_RATIO: RangeInclusive<f32> = 0.1 ..=   10.0;
+    pub(crate) const HEATING:             RangeInclusive<f32> = 0.0 ..=  100.0;
+    pub(crate) const DIFFUSIVITY:         RangeInclusive<f32> = 0.0 ..= 10000.0;
+    pub(crate) const EXPANSION:           RangeInclusive<f32> = 0.0 ..=    0.1;
+    pub(crate) const BODY_SIZE:           RangeInclusive<f32> = 10.0 ..= 300.0;
+    pub(crate) const BODY_DENSITY_RATIO:  RangeInclusive<f32> = 0.1 ..=   10.0;
 }
 
 impl Default for Settings
@@ -52,6 +106,34 @@ impl Default for Settings
             border_damping: *Settings::BORDER_DAMPING.lower_value().unwrap(),
             gravity: Settings::GRAVITY.some_in_range(9.8).unwrap(),
             force_multiplier: Settings::FORCE_MULTIPLIER.some_in_range(32.0).unwrap(),
+            deterministic: false,
+            timestep: Settings::TIMESTEP.some_in_range(1.0 / 64.0).unwrap(),
+            seed: 0,
+            jitter: *Settings::JITTER.lower_value().unwrap(),
+            smoothing_radius: Settings::SMOOTHING_RADIUS.some_in_range(100.0).unwrap(),
+            target_density: Settings::TARGET_DENSITY.some_in_range(1.0).unwrap(),
+            pressure_multiplier: Settings::PRESSURE_MULTIPLIER.some_in_range(100.0).unwrap(),
+            surface_tension: *Settings::SURFACE_TENSION.lower_value().unwrap(),
+            surface_tension_model: SurfaceTensionModel::default(),
+            viscosity: *Settings::VISCOSITY.lower_value().unwrap(),
+            rheologies: [PhaseRheology::default(); 2],
+            viscosity_alpha: *Settings::VISCOSITY_ALPHA.lower_value().unwrap(),
+            viscosity_beta: *Settings::VISCOSITY_BETA.lower_value().unwrap(),
+            balsara_switch: true,
+            continuity_density: false,
+            density_diffusion_model: DensityDiffusionModel::default(),
+            density_diffusion: Settings::DENSITY_DIFFUSION.some_in_range(0.1).unwrap(),
+            shifting: *Settings::SHIFTING.lower_value().unwrap(),
+            two_phases: false,
+            phase_density_ratio: Settings::PHASE_DENSITY_RATIO.some_in_range(2.0).unwrap(),
+            heating: *Settings::HEATING.lower_value().unwrap(),
+            diffusivity: Settings::DIFFUSIVITY.some_in_range(1000.0).unwrap(),
+            expansion: Settings::EXPANSION.some_in_range(0.01).unwrap(),
+            show_temperature: false,
+            show_free_surface: false,
+            body_shape: BodyShape::default(),
+            body_size: Settings::BODY_SIZE.some_in_range(80.0).unwrap(),
+            body_density_ratio: Settings::BODY_DENSITY_RATIO.some_in_range(0.5).unwrap(),
         }
     }
 }
@@ -80,6 +162,337 @@ impl Settings
         let grid_hei = (radius * 2.0 + sep) * count_y - sep;
         Vec2::new(radius-grid_wid/2.0, radius-grid_hei/2.0)
     }
+
+    /// The mass of a particle, taken as the area of its disc so that densities
+    /// are the fraction of space covered by particles.
+    ///
+    pub(crate) fn particle_mass(&self) -> f32
+    {
+        std::f32::consts::PI * self.particle_radius.powi(2)
+    }
+
+    /// The mass of a particle of a phase, scaled by the ratio of the density
+    /// of the phase to that of the first.
+    ///
+    pub(crate) fn phase_mass(&self, phase: usize) -> f32
+    {
+        match phase
+        {
+            0 => self.particle_mass(),
+            _ => self.particle_mass() * self.phase_density_ratio,
+        }
+    }
+
+    /// The density of a rigid body, scaled from that of the first phase by
+    /// the body density ratio, so a body floats below a ratio of one and sinks
+    /// above it.
+    ///
+    /// The density is kept above that of the least target density and ratio,
+    /// as a body without mass cannot move, even when the settings come from a
+    /// checkpoint outside the ranges of the ui.
+    ///
+    pub(crate) fn body_density(&self) -> f32
+    {
+        let least_density = Settings::TARGET_DENSITY.lower_value().unwrap()
+            * Settings::BODY_DENSITY_RATIO.lower_value().unwrap();
+
+        (self.target_density * self.body_density_ratio).max(least_density)
+    }
+
+    /// The phases of the fluid: the first fills the particle grid, and the
+    /// second, when there are two phases, fills its upper half. Every phase
+    /// has its own rheology.
+    ///
+    pub(crate) fn phases(&self) -> Vec<Phase>
+    {
+        let phase = |rest_density: f32, colour: [f32;3], rheology: &PhaseRheology|
+        {
+            let equation_of_state = EquationOfState::Linear {
+                rest_density: rest_density as f64,
+                stiffness: self.pressure_multiplier as f64,
+            };
+
+            Phase::new(equation_of_state)
+                .with_viscosity(self.viscosity as f64)
+                .with_rheology(rheology.rheology())
+                .with_colour(colour)
+                .with_diffusivity(self.diffusivity as f64)
+                .with_expansion(self.expansion as f64, 0.0)
+        };
+
+        vec![
+            phase(self.target_density, [0.0, 1.0, 1.0], &self.rheologies[0]),
+            phase(self.target_density * self.phase_density_ratio, [1.0, 0.5, 0.0], &self.rheologies[1]),
+        ]
+    }
+
+    /// The solver of the forces within the fluid, with the smoothing radius,
+    /// phases, surface tension, artificial viscosity, density evolution,
+    /// particle shifting and gravity of the settings.
+    ///
+    /// When heating, the floor of the domain is held at the heating
+    /// temperature and the ceiling at its negative, so the fluid convects
+    /// between them.
+    ///
+    pub(crate) fn solver(&self, domain: &Domain) -> Solver<2>
+    {
+        let kernel = FieldKernel::new(WendlandC2, self.smoothing_radius as f64, NORMALISATION_TOLERANCE);
+
+        let coefficient = self.surface_tension as f64;
+        let surface_tension = match self.surface_tension_model
+        {
+            _ if coefficient == 0.0 => SurfaceTension::None,
+            SurfaceTensionModel::ColourField => SurfaceTension::ColourField { coefficient, threshold: 0.1 },
+            SurfaceTensionModel::Akinci => SurfaceTension::Akinci { coefficient },
+        };
+
+        let artificial_viscosity = ArtificialViscosity {
+            alpha: self.viscosity_alpha as f64,
+            beta: self.viscosity_beta as f64,
+            balsara: self.balsara_switch,
+        };
+
+        let density_evolution = match self.continuity_density
+        {
+            true => DensityEvolution::Continuity,
+            false => DensityEvolution::Summation,
+        };
+
+        let delta = self.density_diffusion as f64;
+        let density_diffusion = match self.density_diffusion_model
+        {
+            DensityDiffusionModel::MolteniColagrossi => DensityDiffusion::MolteniColagrossi { delta },
+            DensityDiffusionModel::Antuono => DensityDiffusion::Antuono { delta },
+        };
+
+        let heaters = match self.heating
+        {
+            0.0 => Vec::new(),
+            heating =>
+            {
+                let (x, y) = (domain.size.x / 2.0, domain.size.y / 2.0);
+                let wall = |y: f32, temperature: f32|
+                {
+                    let vertices = [nalgebra::Vector2::new(-x, y), nalgebra::Vector2::new(x, y)];
+                    Collider::polyline(&vertices, self.particle_radius, &kernel)
+                        .with_temperature(temperature as f64)
+                };
+
+                vec![wall(-y, heating), wall(y, -heating)]
+            },
+        };
+
+        Solver {
+            surface_tension,
+            artificial_viscosity,
+            density_evolution,
+            density_diffusion,
+            shifting: ParticleShifting::new(self.shifting as f64),
+            colliders: heaters,
+            gravity: nalgebra::Vector2::new(0.0, -self.gravity as f64),
+            ..Solver::new(kernel, self.phases())
+        }
+    }
+}
+
+/// The model of surface tension the solver uses.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SurfaceTensionModel
+{
+    /// The continuum surface force of a colour field.
+    ColourField,
+    /// The cohesion and curvature forces of Akinci et al.
+    #[default] Akinci,
+}
+
+impl SurfaceTensionModel
+{
+    pub(crate) const ALL: [SurfaceTensionModel;2] = [SurfaceTensionModel::ColourField, SurfaceTensionModel::Akinci];
+
+    pub(crate) fn name(&self) -> &'static str
+    {
+        match self
+        {
+            SurfaceTensionModel::ColourField => "Colour Field",
+            SurfaceTensionModel::Akinci => "Akinci",
+        }
+    }
+}
+
+/// The rheology of a phase of the fluid, the model of how its viscosity
+/// depends on its shear rate and the parameters of the model.
+///
+/// ## Fields
+///
+/// * `model`         - The rheology model.
+/// * `flow_index`    - The flow index of a power law, Cross or Carreau fluid.
+/// * `time_constant` - The time constant of a Cross or Carreau fluid.
+/// * `yield_stress`  - The yield stress of a Bingham plastic.
+///
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PhaseRheology
+{
+    pub model: RheologyModel,
+    pub flow_index: f32,
+    pub time_constant: f32,
+    pub yield_stress: f32,
+}
+
+impl Default for PhaseRheology
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            model: RheologyModel::default(),
+            flow_index: Settings::FLOW_INDEX.some_in_range(0.5).unwrap(),
+            time_constant: Settings::TIME_CONSTANT.some_in_range(1.0).unwrap(),
+            yield_stress: Settings::YIELD_STRESS.some_in_range(100.0).unwrap(),
+        }
+    }
+}
+
+impl PhaseRheology
+{
+    /// The rheology of the phase, from the rheology model and its parameters.
+    ///
+    /// A power law fluid is limited to the greatest viscosity of the
+    /// settings, Cross and Carreau fluids thin down to no viscosity at all,
+    /// and a Bingham plastic is regularised so that at rest it has the
+    /// viscosity of its yield stress.
+    ///
+    pub(crate) fn rheology(&self) -> Rheology
+    {
+        let index = self.flow_index as f64;
+        let time_constant = self.time_constant as f64;
+
+        match self.model
+        {
+            RheologyModel::Newtonian => Rheology::Newtonian,
+            RheologyModel::PowerLaw => Rheology::PowerLaw {
+                index,
+                max_viscosity: *Settings::VISCOSITY.upper_value().unwrap() as f64,
+            },
+            RheologyModel::Cross => Rheology::Cross { infinite_viscosity: 0.0, time_constant, index },
+            RheologyModel::Carreau => Rheology::Carreau { infinite_viscosity: 0.0, time_constant, index },
+            RheologyModel::Bingham => Rheology::Bingham {
+                yield_stress: self.yield_stress as f64,
+                regularisation: 1.0,
+            },
+        }
+    }
+}
+
+/// The model of how the viscosity of the fluid depends on its shear rate.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum RheologyModel
+{
+    /// A constant viscosity, like water.
+    #[default] Newtonian,
+    /// A power law of the shear rate, like honey when shear thinning.
+    PowerLaw,
+    /// The Cross model of a shear thinning fluid.
+    Cross,
+    /// The Carreau model of a shear thinning fluid, like paint.
+    Carreau,
+    /// A Bingham plastic with a yield stress, like mud.
+    Bingham,
+}
+
+impl RheologyModel
+{
+    pub(crate) const ALL: [RheologyModel;5] = [
+        RheologyModel::Newtonian,
+        RheologyModel::PowerLaw,
+        RheologyModel::Cross,
+        RheologyModel::Carreau,
+        RheologyModel::Bingham,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str
+    {
+        match self
+        {
+            RheologyModel::Newtonian => "Newtonian",
+            RheologyModel::PowerLaw => "Power Law",
+            RheologyModel::Cross => "Cross",
+            RheologyModel::Carreau => "Carreau",
+            RheologyModel::Bingham => "Bingham",
+        }
+    }
+}
+
+/// The model of δ-SPH density diffusion the solver uses under continuity
+/// density.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum DensityDiffusionModel
+{
+    /// The diffusion of Molteni and Colagrossi.
+    MolteniColagrossi,
+    /// The diffusion of Antuono et al., which keeps the hydrostatic gradient.
+    #[default] Antuono,
+}
+
+impl DensityDiffusionModel
+{
+    pub(crate) const ALL: [DensityDiffusionModel;2] = [DensityDiffusionModel::MolteniColagrossi, DensityDiffusionModel::Antuono];
+
+    pub(crate) fn name(&self) -> &'static str
+    {
+        match self
+        {
+            DensityDiffusionModel::MolteniColagrossi => "Molteni-Colagrossi",
+            DensityDiffusionModel::Antuono => "Antuono",
+        }
+    }
+}
+
+/// The shape of the rigid body dropped into the fluid, if any.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum BodyShape
+{
+    /// No rigid body.
+    #[default] None,
+    /// A circle, of a radius of the body size.
+    Circle,
+    /// A square, of a half width of the body size.
+    Square,
+    /// An equilateral triangle, with its corners the body size from its
+    /// centre.
+    Triangle,
+}
+
+impl BodyShape
+{
+    pub(crate) const ALL: [BodyShape;4] = [BodyShape::None, BodyShape::Circle, BodyShape::Square, BodyShape::Triangle];
+
+    pub(crate) fn name(&self) -> &'static str
+    {
+        match self
+        {
+            BodyShape::None => "None",
+            BodyShape::Circle => "Circle",
+            BodyShape::Square => "Square",
+            BodyShape::Triangle => "Triangle",
+        }
+    }
+}
+
+/// Run condition for systems that step the simulation on a fixed timestep.
+///
+/// In deterministic mode the simulation steps in the `FixedUpdate` schedule,
+/// so a given scene, seed and step count always produce the same particles.
+/// Otherwise it steps once per frame by the variable frame time.
+///
+pub(crate) fn in_deterministic_mode(
+    settings: Res<Settings>,
+) -> bool
+{
+    settings.deterministic
 }
 
 #[derive(Event, PartialEq)]
@@ -91,4 +504,35 @@ pub(crate) enum SettingsChangedEvent
     BorderDamping,
     Gravity,
     ForceMultiplier,
+    Deterministic,
+    Timestep,
+    Seed,
+    Jitter,
+    SmoothingRadius,
+    TargetDensity,
+    PressureMultiplier,
+    SurfaceTension,
+    SurfaceTensionModel,
+    Viscosity,
+    RheologyModel,
+    FlowIndex,
+    TimeConstant,
+    YieldStress,
+    ViscosityAlpha,
+    ViscosityBeta,
+    BalsaraSwitch,
+    ContinuityDensity,
+    DensityDiffusionModel,
+    DensityDiffusion,
+    Shifting,
+    TwoPhases,
+    PhaseDensityRatio,
+    Heating,
+    Diffusivity,
+    Expansion,
+    ShowTemperature,
+    ShowFreeSurface,
+    BodyShape,
+    BodySize,
+    BodyDensityRatio,
 }
@@ -1,32 +1,182 @@
 
 use bevy::prelude::*;
+use bevy::window::*;
+use serde::{Deserialize, Serialize};
 
+use hydrodynamics::FieldKernel;
+use hydrodynamics::solver::*;
+use util::random::SplitMix64;
+use crate::body::*;
 use crate::settings::*;
 use crate::state::*;
 use crate::particle::*;
 
 pub(crate) struct Simulation;
 
+/// The rectangular region, centred on the origin, that particles are confined
+/// to.
+///
+/// Follows the size of the primary window when there is one.
+///
+#[derive(Resource, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Domain
+{
+    pub size: Vec2,
+}
+
+impl Default for Domain
+{
+    fn default() -> Self
+    {
+        Self { size: Vec2::new(1280.0, 720.0) }
+    }
+}
+
+impl Domain
+{
+    /// The walls around the domain as a collider, with boundary particles
+    /// spaced by a distance, which bodies come into contact with.
+    ///
+    pub(crate) fn walls(&self, spacing: f32, kernel: &FieldKernel<2>) -> Collider<2>
+    {
+        let (x, y) = (self.size.x / 2.0, self.size.y / 2.0);
+        let corners = [
+            nalgebra::Vector2::new(-x, -y),
+            nalgebra::Vector2::new( x, -y),
+            nalgebra::Vector2::new( x,  y),
+            nalgebra::Vector2::new(-x,  y),
+        ];
+
+        Collider::polygon(&corners, spacing, kernel)
+    }
+}
+
+/// The amount of simulated time since the particles were last configured.
+///
+#[derive(Resource, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SimTime
+{
+    pub elapsed: f64,
+    pub steps: u64,
+}
+
+/// The solver of the settings and the walls of the domain, built once and
+/// only rebuilt when the settings or the domain they were built for change,
+/// rather than on every step.
+///
+/// ## Fields
+///
+/// * `settings` - The settings the solver was built from.
+/// * `domain`   - The domain the solver was built for.
+/// * `solver`   - The solver of the settings.
+/// * `walls`    - The walls of the domain, which bodies come into contact
+///   with.
+///
+#[derive(Resource, Clone)]
+pub(crate) struct SolverCache
+{
+    settings: Settings,
+    domain: Domain,
+    solver: Solver<2>,
+    walls: Collider<2>,
+}
+
+impl SolverCache
+{
+    fn new(settings: &Settings, domain: &Domain) -> Self
+    {
+        let solver = settings.solver(domain);
+        let walls = domain.walls(settings.particle_radius, &solver.kernel);
+
+        Self {
+            settings: *settings,
+            domain: *domain,
+            solver,
+            walls,
+        }
+    }
+
+    /// Return the solver of the settings.
+    ///
+    pub(crate) fn solver(&self) -> &Solver<2>
+    {
+        &self.solver
+    }
+
+    /// Return the walls of the domain.
+    ///
+    pub(crate) fn walls(&self) -> &Collider<2>
+    {
+        &self.walls
+    }
+}
+
 impl Plugin for Simulation
 {
     fn build(&self, app: &mut App)
     {
         app.init_state::<SimState>();
+        app.init_resource::<Domain>();
+        app.init_resource::<SimTime>();
 
         app.add_systems(Startup,
-            Simulation::respawn_particle_grid
+            (
+                Simulation::respawn_particle_grid,
+                Simulation::respawn_body,
+            )
             .after(ParticleSystem)
             );
 
+        app.add_systems(Startup, Simulation::update_solver_cache);
+
         app.add_systems(OnEnter(SimState::Configure),
-            Simulation::respawn_particle_grid
-            );
+            (
+                Simulation::respawn_particle_grid,
+                Simulation::respawn_body,
+                Simulation::reset_time,
+            ));
 
         app.add_systems(Update,
-            Simulation::respawn_particle_grid
+            (
+                Simulation::respawn_particle_grid,
+                Simulation::respawn_body,
+            )
             .run_if(on_event::<SettingsChangedEvent>)
             .run_if(in_state(SimState::Configure))
             );
+
+        app.add_systems(Update,
+            (
+                Simulation::fit_domain_to_window,
+                Simulation::update_solver_cache,
+            )
+            .chain()
+            .before(ParticleSystem)
+            );
+
+        app.add_systems(FixedUpdate,
+            Simulation::update_solver_cache
+            .before(ParticleSystem)
+            );
+
+        app.add_systems(PreUpdate,
+            Simulation::apply_timestep
+            .run_if(resource_changed::<Settings>)
+            );
+
+        app.add_systems(Update,
+            Simulation::tick
+            .after(ParticleSystem)
+            .run_if(in_state(SimState::Running))
+            .run_if(not(in_deterministic_mode))
+            );
+
+        app.add_systems(FixedUpdate,
+            Simulation::tick
+            .after(ParticleSystem)
+            .run_if(in_state(SimState::Running))
+            .run_if(in_deterministic_mode)
+            );
     }
 }
 
@@ -35,7 +185,6 @@ impl Simulation
     fn respawn_particle_grid(
         mut commands: Commands,
         particles: Query<Entity, With<Particle>>,
-        particle_resources: Res<ParticleResources>,
         settings: Res<Settings>,
     ){
         for particle in particles.iter()
@@ -43,33 +192,117 @@ impl Simulation
             commands.entity(particle).despawn_recursive();
         }
 
-        for (i,j) in itertools::iproduct!(
+        let mut random = SplitMix64::new(settings.seed);
+        let mut jitter = ||
+        {
+            let jitter_x = random.next_f32() * 2.0 - 1.0;
+            let jitter_y = random.next_f32() * 2.0 - 1.0;
+            Vec2::new(jitter_x, jitter_y) * settings.jitter * settings.grid_size()
+        };
+
+        for (id,(i,j)) in itertools::iproduct!(
             0..settings.particle_count.y,
             0..settings.particle_count.x,
-        ){
+        ).enumerate(){
             let grid_size = settings.grid_size();
             let offset = settings.grid_offsets();
             let y = (i as f32) * grid_size + offset.y;
             let x = (j as f32) * grid_size + offset.x;
 
-            let mesh = Mesh2d(particle_resources.mesh.clone().into());
-            let material = MeshMaterial2d(particle_resources.material.clone());
-
-            let transform = Transform::IDENTITY
-                .with_scale(settings.particle_scale())
-                .with_translation(Vec2::new(x,y).extend(0.0))
-                ;
+            // With two phases, the second fills the upper half of the grid.
+            //
+            let phase = match settings.two_phases && 2 * i >= settings.particle_count.y
+            {
+                true => 1,
+                false => 0,
+            };
 
             let particle = Particle {
                 velocity: Vec2::new(0.0, 0.0),
+                phase,
+                temperature: 0.0,
+                density: 0.0,
             };
 
-            commands.spawn((
-                mesh,
-                material,
-                transform,
-                particle,
-            ));
+            let id = ParticleId(id as u32);
+            let position = Vec2::new(x,y) + jitter();
+            commands.spawn(particle.bundle(id, position, &settings));
+        }
+    }
+
+    /// Drop the body of the settings, if any, into the middle of the domain
+    /// just above the particle grid.
+    ///
+    fn respawn_body(
+        mut commands: Commands,
+        bodies: Query<Entity, With<Body>>,
+        settings: Res<Settings>,
+    ){
+        for body in bodies.iter()
+        {
+            commands.entity(body).despawn_recursive();
+        }
+
+        if settings.body_shape == BodyShape::None { return };
+
+        let grid_top = settings.grid_offsets().y
+            + settings.grid_size() * (settings.particle_count.y as f32 - 1.0)
+            + settings.particle_radius;
+
+        let body = Body::new(settings.body_shape, settings.body_size);
+
+        let position = Vec2::new(0.0, grid_top + settings.body_size + settings.grid_size());
+        commands.spawn(body.bundle(position, 0.0));
+    }
+
+    fn reset_time(
+        mut sim_time: ResMut<SimTime>,
+    ){
+        *sim_time = SimTime::default();
+    }
+
+    fn fit_domain_to_window(
+        window_query: Query<&Window, With<PrimaryWindow>>,
+        mut domain: ResMut<Domain>,
+    ){
+        if let Ok(window) = window_query.get_single()
+        {
+            domain.set_if_neq(Domain { size: window.size() });
+        }
+    }
+
+    /// Rebuild the solver when the settings or the domain differ from those
+    /// it was built for.
+    ///
+    /// Settings are compared by value, as the ui borrows them mutably every
+    /// frame whether or not they change.
+    ///
+    pub(crate) fn update_solver_cache(
+        mut commands: Commands,
+        solver_cache: Option<ResMut<SolverCache>>,
+        settings: Res<Settings>,
+        domain: Res<Domain>,
+    ){
+        match solver_cache
+        {
+            Some(solver_cache) if solver_cache.settings == *settings && solver_cache.domain == *domain => {},
+            Some(mut solver_cache) => *solver_cache = SolverCache::new(&settings, &domain),
+            None => commands.insert_resource(SolverCache::new(&settings, &domain)),
         }
     }
+
+    fn apply_timestep(
+        mut fixed_time: ResMut<Time<Fixed>>,
+        settings: Res<Settings>,
+    ){
+        fixed_time.set_timestep_seconds(settings.timestep as f64);
+    }
+
+    pub(crate) fn tick(
+        mut sim_time: ResMut<SimTime>,
+        time: Res<Time>,
+    ){
+        sim_time.elapsed += time.delta_secs_f64();
+        sim_time.steps += 1;
+    }
 }
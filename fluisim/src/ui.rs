@@ -3,6 +3,8 @@ use bevy::prelude::*;
 use bevy_egui::*;
 use bevy_egui::egui::Widget;
 
+use crate::checkpoint::*;
+use crate::export::*;
 use crate::settings::*;
 use crate::state::*;
 
@@ -14,6 +16,7 @@ impl Plugin for UiSystem
     {
         app.add_plugins(EguiPlugin);
         app.add_systems(Update, UiSystem::redraw);
+        app.add_systems(Update, UiSystem::redraw_files);
     }
 }
 
@@ -124,6 +127,440 @@ impl UiSystem
                 {
                     event_writer.send(SettingsChangedEvent::ForceMultiplier);
                 }
+
+                ui.label("Smoothing Radius:");
+                let slider_smoothing_radius = egui::Slider::new(
+                    &mut settings.smoothing_radius,
+                    Settings::SMOOTHING_RADIUS)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_smoothing_radius.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::SmoothingRadius);
+                }
+
+                ui.label("Target Density:");
+                let slider_target_density = egui::Slider::new(
+                    &mut settings.target_density,
+                    Settings::TARGET_DENSITY)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_target_density.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::TargetDensity);
+                }
+
+                ui.label("Pressure Multiplier:");
+                let slider_pressure_multiplier = egui::Slider::new(
+                    &mut settings.pressure_multiplier,
+                    Settings::PRESSURE_MULTIPLIER)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_pressure_multiplier.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::PressureMultiplier);
+                }
+
+                ui.label("Surface Tension:");
+                let slider_surface_tension = egui::Slider::new(
+                    &mut settings.surface_tension,
+                    Settings::SURFACE_TENSION)
+                    .logarithmic(true)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_surface_tension.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::SurfaceTension);
+                }
+
+                ui.label("Surface Tension Model:");
+                let surface_tension_model = settings.surface_tension_model;
+                egui::ComboBox::from_id_salt("Surface Tension Model")
+                    .selected_text(settings.surface_tension_model.name())
+                    .show_ui(ui, |ui|
+                    {
+                        for model in SurfaceTensionModel::ALL
+                        {
+                            ui.selectable_value(&mut settings.surface_tension_model, model, model.name());
+                        }
+                    });
+                ui.end_row();
+
+                if settings.surface_tension_model != surface_tension_model
+                {
+                    event_writer.send(SettingsChangedEvent::SurfaceTensionModel);
+                }
+
+                ui.label("Viscosity:");
+                let slider_viscosity = egui::Slider::new(
+                    &mut settings.viscosity,
+                    Settings::VISCOSITY)
+                    .logarithmic(true)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_viscosity.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Viscosity);
+                }
+
+                // The rheology of every phase, of the second only when there
+                // are two.
+                //
+                let phase_count = if settings.two_phases { 2 } else { 1 };
+                for phase in 0..phase_count
+                {
+                    let suffix = if settings.two_phases { format!(" (Phase {})", phase + 1) } else { String::new() };
+                    let rheology = &mut settings.rheologies[phase];
+
+                    ui.label(format!("Rheology{}:", suffix));
+                    let rheology_model = rheology.model;
+                    egui::ComboBox::from_id_salt(("Rheology", phase))
+                        .selected_text(rheology.model.name())
+                        .show_ui(ui, |ui|
+                        {
+                            for model in RheologyModel::ALL
+                            {
+                                ui.selectable_value(&mut rheology.model, model, model.name());
+                            }
+                        });
+                    ui.end_row();
+
+                    if rheology.model != rheology_model
+                    {
+                        event_writer.send(SettingsChangedEvent::RheologyModel);
+                    }
+
+                    ui.label(format!("Flow Index{}:", suffix));
+                    let slider_flow_index = ui.add_enabled(
+                        matches!(rheology.model, RheologyModel::PowerLaw | RheologyModel::Cross | RheologyModel::Carreau),
+                        egui::Slider::new(
+                            &mut rheology.flow_index,
+                            Settings::FLOW_INDEX)
+                        );
+                    ui.end_row();
+
+                    if slider_flow_index.changed()
+                    {
+                        event_writer.send(SettingsChangedEvent::FlowIndex);
+                    }
+
+                    ui.label(format!("Time Constant{}:", suffix));
+                    let slider_time_constant = ui.add_enabled(
+                        matches!(rheology.model, RheologyModel::Cross | RheologyModel::Carreau),
+                        egui::Slider::new(
+                            &mut rheology.time_constant,
+                            Settings::TIME_CONSTANT)
+                        .logarithmic(true)
+                        );
+                    ui.end_row();
+
+                    if slider_time_constant.changed()
+                    {
+                        event_writer.send(SettingsChangedEvent::TimeConstant);
+                    }
+
+                    ui.label(format!("Yield Stress{}:", suffix));
+                    let slider_yield_stress = ui.add_enabled(
+                        matches!(rheology.model, RheologyModel::Bingham),
+                        egui::Slider::new(
+                            &mut rheology.yield_stress,
+                            Settings::YIELD_STRESS)
+                        .logarithmic(true)
+                        );
+                    ui.end_row();
+
+                    if slider_yield_stress.changed()
+                    {
+                        event_writer.send(SettingsChangedEvent::YieldStress);
+                    }
+                }
+
+                ui.label("Artificial Viscosity α:");
+                let slider_viscosity_alpha = egui::Slider::new(
+                    &mut settings.viscosity_alpha,
+                    Settings::VISCOSITY_ALPHA)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_viscosity_alpha.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::ViscosityAlpha);
+                }
+
+                ui.label("Artificial Viscosity β:");
+                let slider_viscosity_beta = egui::Slider::new(
+                    &mut settings.viscosity_beta,
+                    Settings::VISCOSITY_BETA)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_viscosity_beta.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::ViscosityBeta);
+                }
+
+                ui.label("Balsara Switch:");
+                let checkbox_balsara_switch = ui.checkbox(
+                    &mut settings.balsara_switch,
+                    "");
+                ui.end_row();
+
+                if checkbox_balsara_switch.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::BalsaraSwitch);
+                }
+
+                ui.label("Continuity Density:");
+                let checkbox_continuity_density = ui.checkbox(
+                    &mut settings.continuity_density,
+                    "");
+                ui.end_row();
+
+                if checkbox_continuity_density.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::ContinuityDensity);
+                }
+
+                ui.label("Density Diffusion Model:");
+                let density_diffusion_model = settings.density_diffusion_model;
+                ui.add_enabled_ui(settings.continuity_density, |ui|
+                {
+                    egui::ComboBox::from_id_salt("Density Diffusion Model")
+                        .selected_text(settings.density_diffusion_model.name())
+                        .show_ui(ui, |ui|
+                        {
+                            for model in DensityDiffusionModel::ALL
+                            {
+                                ui.selectable_value(&mut settings.density_diffusion_model, model, model.name());
+                            }
+                        });
+                });
+                ui.end_row();
+
+                if settings.density_diffusion_model != density_diffusion_model
+                {
+                    event_writer.send(SettingsChangedEvent::DensityDiffusionModel);
+                }
+
+                ui.label("Density Diffusion:");
+                let slider_density_diffusion = ui.add_enabled(
+                    settings.continuity_density,
+                    egui::Slider::new(
+                        &mut settings.density_diffusion,
+                        Settings::DENSITY_DIFFUSION)
+                    );
+                ui.end_row();
+
+                if slider_density_diffusion.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::DensityDiffusion);
+                }
+
+                ui.label("Shifting:");
+                let slider_shifting = egui::Slider::new(
+                    &mut settings.shifting,
+                    Settings::SHIFTING)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_shifting.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Shifting);
+                }
+
+                ui.label("Two Phases:");
+                let checkbox_two_phases = ui.add_enabled(
+                    matches!(state_reader.get(), SimState::Configure),
+                    egui::Checkbox::without_text(&mut settings.two_phases)
+                    );
+                ui.end_row();
+
+                if checkbox_two_phases.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::TwoPhases);
+                }
+
+                ui.label("Phase Density Ratio:");
+                let slider_phase_density_ratio = ui.add_enabled(
+                    settings.two_phases,
+                    egui::Slider::new(
+                        &mut settings.phase_density_ratio,
+                        Settings::PHASE_DENSITY_RATIO)
+                    .logarithmic(true)
+                    );
+                ui.end_row();
+
+                if slider_phase_density_ratio.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::PhaseDensityRatio);
+                }
+
+                ui.label("Heating:");
+                let slider_heating = egui::Slider::new(
+                    &mut settings.heating,
+                    Settings::HEATING)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_heating.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Heating);
+                }
+
+                ui.label("Diffusivity:");
+                let slider_diffusivity = egui::Slider::new(
+                    &mut settings.diffusivity,
+                    Settings::DIFFUSIVITY)
+                    .logarithmic(true)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_diffusivity.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Diffusivity);
+                }
+
+                ui.label("Expansion:");
+                let slider_expansion = egui::Slider::new(
+                    &mut settings.expansion,
+                    Settings::EXPANSION)
+                    .ui(ui);
+                ui.end_row();
+
+                if slider_expansion.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Expansion);
+                }
+
+                ui.label("Show Temperature:");
+                let checkbox_show_temperature = ui.add_enabled(
+                    settings.heating > 0.0,
+                    egui::Checkbox::without_text(&mut settings.show_temperature)
+                    );
+                ui.end_row();
+
+                if checkbox_show_temperature.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::ShowTemperature);
+                }
+
+                ui.label("Show Free Surface:");
+                let checkbox_show_free_surface = ui.checkbox(
+                    &mut settings.show_free_surface,
+                    "");
+                ui.end_row();
+
+                if checkbox_show_free_surface.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::ShowFreeSurface);
+                }
+
+                ui.label("Body:");
+                let body_shape = settings.body_shape;
+                ui.add_enabled_ui(matches!(state_reader.get(), SimState::Configure), |ui|
+                {
+                    egui::ComboBox::from_id_salt("Body")
+                        .selected_text(settings.body_shape.name())
+                        .show_ui(ui, |ui|
+                        {
+                            for shape in BodyShape::ALL
+                            {
+                                ui.selectable_value(&mut settings.body_shape, shape, shape.name());
+                            }
+                        });
+                });
+                ui.end_row();
+
+                if settings.body_shape != body_shape
+                {
+                    event_writer.send(SettingsChangedEvent::BodyShape);
+                }
+
+                ui.label("Body Size:");
+                let slider_body_size = ui.add_enabled(
+                    matches!(state_reader.get(), SimState::Configure) && settings.body_shape != BodyShape::None,
+                    egui::Slider::new(
+                        &mut settings.body_size,
+                        Settings::BODY_SIZE)
+                    );
+                ui.end_row();
+
+                if slider_body_size.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::BodySize);
+                }
+
+                ui.label("Body Density Ratio:");
+                let slider_body_density_ratio = ui.add_enabled(
+                    settings.body_shape != BodyShape::None,
+                    egui::Slider::new(
+                        &mut settings.body_density_ratio,
+                        Settings::BODY_DENSITY_RATIO)
+                    );
+                ui.end_row();
+
+                if slider_body_density_ratio.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::BodyDensityRatio);
+                }
+
+                ui.label("Deterministic:");
+                let checkbox_deterministic = ui.checkbox(
+                    &mut settings.deterministic,
+                    "");
+                ui.end_row();
+
+                if checkbox_deterministic.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Deterministic);
+                }
+
+                ui.label("Timestep:");
+                let slider_timestep = ui.add_enabled(
+                    settings.deterministic,
+                    egui::Slider::new(
+                        &mut settings.timestep,
+                        Settings::TIMESTEP)
+                    .logarithmic(true)
+                    );
+                ui.end_row();
+
+                if slider_timestep.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Timestep);
+                }
+
+                ui.label("Seed:");
+                let slider_seed = ui.add_enabled(
+                    matches!(state_reader.get(), SimState::Configure),
+                    egui::DragValue::new(
+                        &mut settings.seed)
+                    );
+                ui.end_row();
+
+                if slider_seed.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Seed);
+                }
+
+                ui.label("Jitter:");
+                let slider_jitter = ui.add_enabled(
+                    matches!(state_reader.get(), SimState::Configure),
+                    egui::Slider::new(
+                        &mut settings.jitter,
+                        Settings::JITTER)
+                    );
+                ui.end_row();
+
+                if slider_jitter.changed()
+                {
+                    event_writer.send(SettingsChangedEvent::Jitter);
+                }
             });
 
             ui.horizontal(|ui|
@@ -157,4 +594,71 @@ impl UiSystem
             });
         });
     }
+
+    fn redraw_files(
+        mut contexts: EguiContexts,
+        mut checkpoint_writer: EventWriter<CheckpointEvent>,
+        mut checkpoint_file: ResMut<CheckpointFile>,
+        mut exporter: ResMut<Exporter>,
+    ){
+        let window = egui::Window::new("Files");
+
+        window.show(contexts.ctx_mut(), |ui|
+        {
+            ui.horizontal(|ui|
+            {
+                ui.label("Checkpoint:");
+                ui.text_edit_singleline(&mut checkpoint_file.0);
+
+                if ui.button("Save").clicked()
+                {
+                    let path = checkpoint_file.0.clone().into();
+                    checkpoint_writer.send(CheckpointEvent::Save(path));
+                }
+
+                if ui.button("Load").clicked()
+                {
+                    let path = checkpoint_file.0.clone().into();
+                    checkpoint_writer.send(CheckpointEvent::Restore(path));
+                }
+            });
+
+            ui.horizontal(|ui|
+            {
+                ui.label("Export:");
+                ui.add_enabled_ui(!exporter.is_recording(), |ui|
+                {
+                    ui.text_edit_singleline(&mut exporter.directory);
+
+                    egui::ComboBox::from_id_salt("Export Format")
+                        .selected_text(exporter.format.name())
+                        .show_ui(ui, |ui|
+                        {
+                            for format in ExportFormat::ALL
+                            {
+                                ui.selectable_value(&mut exporter.format, format, format.name());
+                            }
+                        });
+                });
+
+                let button_record = match exporter.is_recording()
+                {
+                    false => ui.button("Record"),
+                    true => ui.button("Stop"),
+                };
+
+                if button_record.clicked()
+                {
+                    match exporter.is_recording()
+                    {
+                        false => if let Err(error) = exporter.start()
+                        {
+                            error!("Failed to export to {}: {}", exporter.directory, error);
+                        },
+                        true => exporter.stop(),
+                    }
+                }
+            });
+        });
+    }
 }
@@ -0,0 +1,79 @@
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use hydrodynamics::kernels::*;
+use hydrodynamics::*;
+
+type Position = nalgebra::SVector<f32,2>;
+
+const SUPPORT: f64 = 20.0;
+
+/// Scatter particles over a square, with roughly thirty neighbours within the
+/// kernel support of every particle.
+///
+fn positions(count: usize) -> Vec<Position>
+{
+    let width = (count as f64 * SUPPORT.powi(2) / 30.0).sqrt() as f32;
+    let mut random = util::random::SplitMix64::new(0);
+
+    (0..count)
+        .map(|_| Position::new(random.next_f32() * width, random.next_f32() * width))
+        .collect()
+}
+
+fn influence(c: &mut Criterion)
+{
+    let radii = (0..1024)
+        .map(|i| i as f64 / 1024.0 * SUPPORT)
+        .collect::<Vec<_>>();
+
+    let dynamic = FieldKernel::<2>::new(Poly6, SUPPORT, NORMALISATION_TOLERANCE);
+    let r#static = FieldKernel::<2,Poly6>::new_static(Poly6, SUPPORT, NORMALISATION_TOLERANCE);
+    let linear = dynamic.clone().tabulated(1024, Interpolation::Linear);
+    let cubic = dynamic.clone().tabulated(1024, Interpolation::Cubic);
+
+    let mut group = c.benchmark_group("influence");
+    group.bench_function("dynamic", |b| b.iter(||
+    {
+        radii.iter().map(|r| dynamic.influence(std::hint::black_box(*r))).sum::<f64>()
+    }));
+    group.bench_function("static", |b| b.iter(||
+    {
+        radii.iter().map(|r| r#static.influence(std::hint::black_box(*r))).sum::<f64>()
+    }));
+    group.bench_function("tabulated linear", |b| b.iter(||
+    {
+        radii.iter().map(|r| linear.influence(std::hint::black_box(*r))).sum::<f64>()
+    }));
+    group.bench_function("tabulated cubic", |b| b.iter(||
+    {
+        radii.iter().map(|r| cubic.influence(std::hint::black_box(*r))).sum::<f64>()
+    }));
+    group.finish();
+}
+
+fn densities(c: &mut Criterion)
+{
+    let mut group = c.benchmark_group("densities");
+
+    for count in [1_000, 10_000]
+    {
+        let particles = ParticleSet::from_positions(positions(count), 1.0);
+        let dynamic = UniformField::new(FieldKernel::<2>::new(Poly6, SUPPORT, NORMALISATION_TOLERANCE), &particles);
+        let r#static = UniformField::new(FieldKernel::<2,Poly6>::new_static(Poly6, SUPPORT, NORMALISATION_TOLERANCE), &particles);
+
+        group.bench_with_input(BenchmarkId::new("dynamic", count), &dynamic, |b, field|
+        {
+            b.iter(|| field.densities())
+        });
+        group.bench_with_input(BenchmarkId::new("static", count), &r#static, |b, field|
+        {
+            b.iter(|| field.densities())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, influence, densities);
+criterion_main!(benches);
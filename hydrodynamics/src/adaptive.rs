@@ -0,0 +1,215 @@
+
+use crate::{FieldKernel, Kernel, NeighbourGrid, ParticleSet};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldPos<const N: usize> = nalgebra::SVector<f32,N>;
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// Represents a field of particles in N-dimensional space, each smoothed by a
+/// kernel with its own radius of support.
+///
+/// The kernel between two particles is symmetrised as the mean of the kernel
+/// at either radius of support, `W_ij = (W(r, h_i) + W(r, h_j)) / 2`, so
+/// pairwise forces built from it conserve momentum. The neighbour grid is
+/// sized to the largest radius of support, so every pair within either radius
+/// is found.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+/// * `K` - The type of the smoothing kernel.
+///
+/// ## Fields
+///
+/// * `kernel`     - The field kernel, whose shape is used at every radius.
+/// * `particles`  - The particles contributing to the field.
+/// * `neighbours` - The particles bucketed by position.
+///
+pub struct AdaptiveField<'a, const N: usize, K: ?Sized + Kernel = dyn Kernel>
+{
+    kernel: FieldKernel<N,K>,
+    particles: &'a ParticleSet<N>,
+    neighbours: NeighbourGrid<N>,
+}
+
+impl<'a, const N: usize, K: ?Sized + Kernel> AdaptiveField<'a,N,K>
+{
+    /// Create a new field of the particles in a particle set, with the radius
+    /// of support of every particle taken from the set.
+    ///
+    pub fn new(kernel: FieldKernel<N,K>, particles: &'a ParticleSet<N>) -> Self
+    {
+        let largest_radius = particles.support_radii.iter()
+            .copied()
+            .fold(0.0, f64::max);
+
+        let mut neighbours = NeighbourGrid::new(largest_radius);
+
+        for (index, position) in particles.positions.iter().enumerate()
+        {
+            neighbours.insert(index, position);
+        }
+
+        Self {
+            kernel,
+            particles,
+            neighbours,
+        }
+    }
+
+    /// Return the particles contributing to the field.
+    ///
+    pub fn particles(&self) -> &'a ParticleSet<N>
+    {
+        self.particles
+    }
+
+    /// Return the index of, and offset to, every particle within a distance
+    /// of a position.
+    ///
+    fn neighbours_within(&self, position: &FieldPos<N>, radius: f64) -> impl Iterator<Item = (usize, FieldVec<N>)> + '_
+    {
+        let position = *position;
+
+        self.neighbours.candidates(&position)
+            .map(move |index| (index, (self.particles.positions[index] - position).map(f64::from)))
+            .filter(move |(_index, offset)| offset.norm() <= radius)
+    }
+
+    /// Return the index of, and offset to, every particle within the radius of
+    /// support of either itself or a particle.
+    ///
+    pub fn neighbours_of(&self, index: usize) -> impl Iterator<Item = (usize, FieldVec<N>)> + '_
+    {
+        let support = self.particles.support_radii[index];
+
+        self.neighbours_within(&self.particles.positions[index], self.neighbours.cell_size())
+            .filter(move |(other, offset)|
+            {
+                offset.norm() <= support.max(self.particles.support_radii[*other])
+            })
+    }
+
+    /// Return the symmetrised influence between two particles a distance `r`
+    /// apart.
+    ///
+    pub fn influence(&self, index: usize, other: usize, r: f64) -> f64
+    {
+        let support = self.particles.support_radii[index];
+        let support_other = self.particles.support_radii[other];
+
+        0.5 * (self.kernel.influence_with_support(r, support)
+            + self.kernel.influence_with_support(r, support_other))
+    }
+
+    /// Return the gradient of the symmetrised influence between two particles
+    /// with respect to the position of the first, given the offset from the
+    /// first to the second.
+    ///
+    pub fn influence_gradient(&self, index: usize, other: usize, offset: &FieldVec<N>) -> FieldVec<N>
+    {
+        let r = offset.norm();
+        if r == 0.0 { return FieldVec::zeros() };
+
+        let support = self.particles.support_radii[index];
+        let support_other = self.particles.support_radii[other];
+
+        let derivative = 0.5 * (self.kernel.influence_derivative_with_support(r, support)
+            + self.kernel.influence_derivative_with_support(r, support_other));
+
+        -offset * (derivative / r)
+    }
+
+    /// Evaluate the density at a particle from the symmetrised influence of
+    /// its neighbours.
+    ///
+    pub fn density(&self, index: usize) -> f64
+    {
+        self.neighbours_of(index)
+            .map(|(other, offset)|
+            {
+                self.particles.masses[other] * self.influence(index, other, offset.norm())
+            })
+            .sum()
+    }
+
+    /// Evaluate the density at every particle from the symmetrised influence
+    /// of its neighbours, in the order of the particle set.
+    ///
+    pub fn densities(&self) -> Vec<f64>
+    {
+        maybe_par_iter!(0..self.particles.len())
+            .map(|index| self.density(index))
+            .collect()
+    }
+
+    /// Evaluate the density at every particle with only its own radius of
+    /// support, as used to adapt the radii of support to the density.
+    ///
+    pub fn gather_densities(&self) -> Vec<f64>
+    {
+        maybe_par_iter!(0..self.particles.len())
+            .map(|index|
+            {
+                let position = &self.particles.positions[index];
+                let support = self.particles.support_radii[index];
+
+                self.neighbours_within(position, support)
+                    .map(|(other, offset)|
+                    {
+                        self.particles.masses[other] * self.kernel.influence_with_support(offset.norm(), support)
+                    })
+                    .sum::<f64>()
+            })
+            .collect()
+    }
+
+    /// Evaluate the derivative of the density at every particle with only its
+    /// own radius of support, with respect to that radius, in the order of the
+    /// particle set.
+    ///
+    /// The kernel is self-similar, so `∂W/∂h = -(N W + r ∂W/∂r) / h`.
+    ///
+    pub fn gather_density_derivatives(&self) -> Vec<f64>
+    {
+        maybe_par_iter!(0..self.particles.len())
+            .map(|index|
+            {
+                let position = &self.particles.positions[index];
+                let support = self.particles.support_radii[index];
+
+                self.neighbours_within(position, support)
+                    .map(|(other, offset)|
+                    {
+                        let r = offset.norm();
+                        let influence = self.kernel.influence_with_support(r, support);
+                        let derivative = self.kernel.influence_derivative_with_support(r, support);
+
+                        -self.particles.masses[other] * (N as f64 * influence + r * derivative) / support
+                    })
+                    .sum::<f64>()
+            })
+            .collect()
+    }
+
+    /// Interpolate a quantity of every particle at a position, with every
+    /// particle scattering its quantity over its own radius of support.
+    ///
+    /// Samples are weighted by the densities of the particle set, so those
+    /// must be up to date.
+    ///
+    pub fn interpolate(&self, quantities: &[f64], position: FieldPos<N>) -> f64
+    {
+        self.neighbours_within(&position, self.neighbours.cell_size())
+            .map(|(index, offset)|
+            {
+                let support = self.particles.support_radii[index];
+                let influence = self.kernel.influence_with_support(offset.norm(), support);
+                let volume = self.particles.masses[index] / self.particles.densities[index];
+                quantities[index] * influence * volume
+            })
+            .sum()
+    }
+}
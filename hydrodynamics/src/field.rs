@@ -1,176 +1,334 @@
+use std::borrow::Cow;
+
 use util::to_array::*;
-use crate::FieldKernel;
+use crate::{FieldKernel, Kernel, NeighbourGrid, ParticleSet};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 type FieldPos<const N: usize> = nalgebra::SVector<f32,N>;
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// The correction applied to the gradient of a quantity field evaluated from
+/// the gradient of the kernel.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GradientCorrection
+{
+    /// The uncorrected difference of quantities, exact for constant fields.
+    #[default] None,
+    /// The difference of quantities renormalised by the inverse of the kernel
+    /// gradient moment matrix `L`, exact for linear fields even where the
+    /// support of a particle is truncated by a free surface or boundary.
+    Renormalised,
+}
 
-/// Represents a uniform-mass field of particles in N-dimensional space.
+/// Represents a field of particles in N-dimensional space, smoothed by a
+/// kernel of uniform support.
+///
+/// The field borrows its particles from a particle set, and only owns the
+/// grid used to find the neighbours of a position.
 ///
 /// ## Type Parameters
 ///
 /// * `N` - The number of dimensions in the space.
-/// * `T` - The type of particles contributing to the field.
+/// * `K` - The type of the smoothing kernel.
 ///
 /// ## Fields
 ///
-/// * `kernel`    - The field kernel.
-/// * `particles` - A vector of particles, and their field data.
+/// * `kernel`     - The field kernel.
+/// * `particles`  - The particles contributing to the field.
+/// * `neighbours` - The particles bucketed by position.
 ///
-pub struct UniformField<const N: usize, T>
+pub struct UniformField<'a, const N: usize, K: ?Sized + Kernel = dyn Kernel>
 {
-    kernel: FieldKernel<N>,
-    particles: Vec<(FieldPos<N>,T)>, // (position, particle)
+    kernel: FieldKernel<N,K>,
+    particles: &'a ParticleSet<N>,
+    neighbours: NeighbourGrid<N>,
 }
 
-impl<const N: usize, T> UniformField<N,T>
+impl<'a, const N: usize, K: ?Sized + Kernel> UniformField<'a,N,K>
 {
-    /// Create a new uniform-mass field.
+    /// Create a new field of the particles in a particle set.
     ///
-    pub fn new(kernel: FieldKernel<N>) -> Self
+    pub fn new(kernel: FieldKernel<N,K>, particles: &'a ParticleSet<N>) -> Self
     {
+        let mut neighbours = NeighbourGrid::new(kernel.support_radius());
+
+        for (index, position) in particles.positions.iter().enumerate()
+        {
+            neighbours.insert(index, position);
+        }
+
         Self {
             kernel,
-            particles: Vec::new(),
+            particles,
+            neighbours,
         }
     }
 
-    /// Contribute a particle to the field.
+    /// Return the field kernel.
+    ///
+    pub fn kernel(&self) -> &FieldKernel<N,K>
+    {
+        &self.kernel
+    }
+
+    /// Return the particles contributing to the field.
     ///
-    pub fn contribute(&mut self, position: FieldPos<N>, particle: T)
+    pub fn particles(&self) -> &'a ParticleSet<N>
     {
-        self.particles.push(( position, particle ));
+        self.particles
     }
 
     /// Interpolate and evaluate the density at a position based on the
-    /// positions of nearby equal-mass particles.
+    /// positions and masses of nearby particles.
     ///
     pub fn density(&self, position: &FieldPos<N>) -> f64
     {
-        self.particles.iter()
+        self.neighbours.candidates(position)
+            .map(|index| (&self.particles.positions[index], self.particles.masses[index]))
 
             // Calculate the euclidean distance from the desired position.
             //
-            .map(|(position_other, _particle)|
+            .map(|(position_other, mass)|
             {
                 let radius = (position - position_other).map(f64::from).norm();
-                radius
+                (radius, mass)
             })
 
             // Filter only the particles who are within the kernel's support
             // radius.
             //
-            .filter(|radius|
+            .filter(|(radius, _mass)|
             {
                 *radius <= self.kernel.support_radius()
             })
 
             // Calculate the influence this particle has on the density.
             //
-            .map(|radius|
+            .map(|(radius, mass)|
             {
-                let influence = self.kernel.influence(radius);
-                influence
+                mass * self.kernel.influence(radius)
             })
 
             // Return the sum of all contributing influences.
             .sum()
     }
 
-    /// Interpolate a quantity field based on the quantity from all nearby
-    /// particles.
+    /// Evaluate the kernel sum `Σ m/ρ W` at a position, which is one where the
+    /// support of the position is filled with particles and falls towards
+    /// free surfaces and boundaries.
     ///
-    pub fn sample(&self, to_quantity: impl Fn(&T) -> f64) -> UniformQuantityField<N>
+    pub fn kernel_sum(&self, position: &FieldPos<N>) -> f64
     {
-        let quantities = self.particles.iter()
-
-            // Sample the quantity from the particle.
-            //
-            .map(|(position, particle)|
+        self.neighbours_within(position)
+            .map(|(index, offset)|
             {
-                let quantity = to_quantity(particle);
-                (position, quantity)
+                let volume = self.particles.masses[index] / self.particles.densities[index];
+                volume * self.kernel.influence(offset.norm())
             })
+            .sum()
+    }
 
-            // Sample the density at the position of the particle.
-            //
-            .map(|(position, quantity)|
+    /// Return the index of, and offset to, every particle within the kernel's
+    /// support radius of a position.
+    ///
+    pub fn neighbours_within(&self, position: &FieldPos<N>) -> impl Iterator<Item = (usize, FieldVec<N>)> + '_
+    {
+        let position = *position;
+
+        self.neighbours.candidates(&position)
+            .map(move |index| (index, (self.particles.positions[index] - position).map(f64::from)))
+            .filter(|(_index, offset)| offset.norm() <= self.kernel.support_radius())
+    }
+
+    /// Return the gradient of the influence of a particle with respect to a
+    /// position, given the offset from the position to the particle.
+    ///
+    pub fn influence_gradient(&self, offset: &FieldVec<N>) -> FieldVec<N>
+    {
+        let radius = offset.norm();
+        if radius == 0.0 { return FieldVec::zeros() };
+
+        -offset * (self.kernel.influence_derivative(radius) / radius)
+    }
+
+    /// Evaluate the number density, the sum of the influence of every
+    /// particle, at the position of every particle, in the order of the
+    /// particle set.
+    ///
+    /// Unlike the density this does not depend on the mass of neighbouring
+    /// particles, so `ρ_i = m_i Σ W_ij` stays sharp across the interface
+    /// between fluids of different densities.
+    ///
+    pub fn number_densities(&self) -> Vec<f64>
+    {
+        maybe_par_iter!(&self.particles.positions)
+            .map(|position|
             {
-                let density = self.density(&position);
-                (position, density, quantity)
+                self.neighbours_within(position)
+                    .map(|(_index, offset)| self.kernel.influence(offset.norm()))
+                    .sum()
             })
+            .collect()
+    }
 
-            // Collect the samples into a vector.
-            //
-            .map(|(position, density, quantity)|
+    /// Evaluate the density at the position of every particle, in the order
+    /// of the particle set.
+    ///
+    pub fn densities(&self) -> Vec<f64>
+    {
+        maybe_par_iter!(&self.particles.positions)
+            .map(|position| self.density(position))
+            .collect()
+    }
+
+    /// Evaluate the gradient of the velocity at every particle,
+    /// `∇v_i = Σ m/ρ (v_j - v_i) ⊗ ∇W`, in the order of the particle set. Row
+    /// `a` and column `b` of every tensor is `∂v_a / ∂x_b`.
+    ///
+    /// Gradients are weighted by the densities of the particle set, so those
+    /// must be up to date.
+    ///
+    pub fn velocity_gradients(&self) -> Vec<nalgebra::SMatrix<f64,N,N>>
+    {
+        maybe_par_iter!(0..self.particles.len())
+            .map(|index|
             {
-                (position.clone(), density, quantity)
+                let velocity = self.particles.velocities[index].map(f64::from);
+
+                self.neighbours_within(&self.particles.positions[index])
+                    .map(|(other, offset)|
+                    {
+                        let volume = self.particles.masses[other] / self.particles.densities[other];
+                        let relative_velocity = self.particles.velocities[other].map(f64::from) - velocity;
+                        relative_velocity * self.influence_gradient(&offset).transpose() * volume
+                    })
+                    .sum()
             })
-            .collect::<Vec<(FieldPos<N>,f64,f64)>>();
+            .collect()
+    }
+
+    /// Interpolate a quantity field based on a quantity of every particle,
+    /// either borrowed or owned.
+    ///
+    /// Samples are weighted by the densities of the particle set, so those
+    /// must be up to date, as from [`ParticleSet::update_densities`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one quantity per particle.
+    ///
+    pub fn sample<'b>(&'b self, quantities: impl Into<Cow<'b,[f64]>>) -> UniformQuantityField<'b,N,K>
+    {
+        let quantities = quantities.into();
+        assert_eq!(quantities.len(), self.particles.len(), "one quantity per particle");
 
         UniformQuantityField {
-            kernel: self.kernel.clone(),
+            field: self,
             quantities,
+            shepard: false,
         }
     }
+
+    /// Interpolate a quantity field based on a named scalar attribute column of
+    /// the particle set, if present.
+    ///
+    pub fn sample_scalar(&self, name: &str) -> Option<UniformQuantityField<'_,N,K>>
+    {
+        self.particles.scalar(name).map(|quantities| self.sample(quantities))
+    }
 }
 
-/// Represents a uniform-mass field of quantities in N-dimensional space.
+/// Represents a field of quantities in N-dimensional space, sampled from the
+/// particles of a field.
 ///
 /// ## Type Parameters
 ///
 /// * `N`: The number of dimensions in the space (const generic parameter).
+/// * `K`: The type of the smoothing kernel.
 ///
 /// ## Fields
 ///
-/// * `kernel`     - The field kernel.
-/// * `quantities` - A vector of quantities, and their field data.
+/// * `field`      - The field of particles the quantities are sampled from.
+/// * `quantities` - The quantity of every particle.
+/// * `shepard`    - Whether interpolation is Shepard corrected.
 ///
-pub struct UniformQuantityField<const N: usize>
+pub struct UniformQuantityField<'a, const N: usize, K: ?Sized + Kernel = dyn Kernel>
 {
-    kernel: FieldKernel<N>,
-    quantities: Vec<(FieldPos<N>,f64,f64)>, // (position, density, quantity)
+    field: &'a UniformField<'a,N,K>,
+    quantities: Cow<'a,[f64]>,
+    shepard: bool,
 }
 
-impl<const N: usize> UniformQuantityField<N>
+impl<'a, const N: usize, K: ?Sized + Kernel> UniformQuantityField<'a,N,K>
 {
+    /// Apply the Shepard correction when interpolating, dividing by the kernel
+    /// sum so constant fields are reproduced exactly near free surfaces and
+    /// boundaries.
+    ///
+    pub fn shepard_corrected(mut self) -> Self
+    {
+        self.shepard = true;
+        self
+    }
+
     /// Interpolate the quantity of the field at the desired position based on
     /// the quantities from all nearby samples.
     ///
     pub fn at(&self, position: FieldPos<N>) -> f64
     {
-        self.quantities.iter()
+        let particles = self.field.particles;
+
+        let quantity = self.field.neighbours.candidates(&position)
 
             // Calculate the euclidean distance from the desired position.
             //
-            .map(|(position_other, density, quantity)|
+            .map(|index|
             {
-                let radius = (position - position_other).map(f64::from).norm();
-                (radius, density, quantity)
+                let radius = (position - particles.positions[index]).map(f64::from).norm();
+                (radius, index)
             })
 
             // Filter only the quantities who are within the kernel's support
             // radius.
             //
-            .filter(|(radius, _density, _quantity)|
+            .filter(|(radius, _index)|
             {
-                *radius <= self.kernel.support_radius()
+                *radius <= self.field.kernel.support_radius()
             })
 
             // Calculate the influence this sample quantity has on the final
             // quantity.
             //
-            .map(|(radius, density, quantity)|
+            .map(|(radius, index)|
             {
-                let influence = self.kernel.influence(radius);
-                quantity * influence / density
+                let influence = self.field.kernel.influence(radius);
+                let volume = particles.masses[index] / particles.densities[index];
+                self.quantities[index] * influence * volume
             })
 
             // Return the sum of all contributing quantity influences.
-            .sum()
+            .sum::<f64>();
+
+        match self.shepard
+        {
+            true => shepard_correct(quantity, self.field.kernel_sum(&position)),
+            false => quantity,
+        }
+    }
+
+    /// Return the quantity of every particle.
+    ///
+    pub fn quantities(&self) -> &[f64]
+    {
+        &self.quantities
     }
 
     /// Interpolate the gradient of the field based on quantity from all nearby
     /// samples.
-    pub fn gradient(&self, delta: f64) -> UniformGradientField<N>
+    pub fn gradient(&self, delta: f64) -> UniformGradientField<'a,N,K>
     {
         let to_gradient = |position: &FieldPos<N>, quantity: f64|
         {
@@ -188,67 +346,203 @@ impl<const N: usize> UniformQuantityField<N>
             std::array::from_fn(to_partial)
         };
 
-        let gradients = self.quantities.iter()
+        let gradients = maybe_par_iter!(0..self.quantities.len())
 
             // Map the quantity at each position to the gradient of that
             // quantity.
             //
-            .map(|(position, density, quantity)|
+            .map(|index|
             {
-                let gradient = to_gradient(position, *quantity);
-                (position, density, gradient)
+                let position = &self.field.particles.positions[index];
+                to_gradient(position, self.quantities[index])
             })
 
             // Collect the gradients into a vector.
             //
-            .map(|(position, density, gradient)|
+            .collect();
+
+        UniformGradientField {
+            field: self.field,
+            gradients,
+            shepard: false,
+        }
+    }
+
+    /// Evaluate the gradient of the field at every particle from the gradient
+    /// of the kernel, `Σ m/ρ (A_j - A_i) ∇W`, with an optional correction.
+    ///
+    pub fn kernel_gradient(&self, correction: GradientCorrection) -> UniformGradientField<'a,N,K>
+    {
+        let particles = self.field.particles;
+
+        let gradients = maybe_par_iter!(0..self.quantities.len())
+            .map(|index|
             {
-                (position.clone(), density.clone(), gradient)
+                let position = &particles.positions[index];
+                let mut gradient = FieldVec::<N>::zeros();
+                let mut moment = nalgebra::SMatrix::<f64,N,N>::zeros();
+
+                for (other, offset) in self.field.neighbours_within(position)
+                {
+                    let kernel_gradient = self.field.influence_gradient(&offset);
+                    let volume = particles.masses[other] / particles.densities[other];
+
+                    gradient += kernel_gradient * (volume * (self.quantities[other] - self.quantities[index]));
+                    moment += kernel_gradient * offset.transpose() * volume;
+                }
+
+                // A particle with too few neighbours to renormalise keeps the
+                // uncorrected gradient.
+                //
+                let gradient = match correction
+                {
+                    GradientCorrection::None => gradient,
+                    GradientCorrection::Renormalised => renormalise(&moment, &gradient).unwrap_or(gradient),
+                };
+
+                gradient.into()
             })
             .collect();
 
         UniformGradientField {
-            kernel: self.kernel.clone(),
+            field: self.field,
             gradients,
+            shepard: false,
         }
     }
+
+    /// Evaluate the gradient of the field at every particle from the gradient
+    /// of the kernel in summation form, `Σ m/ρ A_j ∇W`.
+    ///
+    /// Unlike the difference form of [`UniformQuantityField::kernel_gradient`]
+    /// this does not vanish for a constant quantity where the support of a
+    /// particle is truncated, so the gradient of a constant colour quantity
+    /// points into the fluid at its free surface.
+    ///
+    pub fn summation_gradient(&self) -> UniformGradientField<'a,N,K>
+    {
+        let particles = self.field.particles;
+
+        let gradients = maybe_par_iter!(0..self.quantities.len())
+            .map(|index|
+            {
+                self.field.neighbours_within(&particles.positions[index])
+                    .map(|(other, offset)|
+                    {
+                        let volume = particles.masses[other] / particles.densities[other];
+                        self.field.influence_gradient(&offset) * (volume * self.quantities[other])
+                    })
+                    .sum::<FieldVec<N>>()
+                    .into()
+            })
+            .collect();
+
+        UniformGradientField {
+            field: self.field,
+            gradients,
+            shepard: false,
+        }
+    }
+
+    /// Evaluate the Laplacian of the field at every particle, in the order of
+    /// the particle set.
+    ///
+    /// Uses the form of Brookshaw (1985),
+    /// `∇²A_i = 2 Σ m/ρ (A_i - A_j) r W'(r) / (r² + ε h²)`, which only needs
+    /// the first derivative of the kernel and conserves the quantity between
+    /// pairs of particles of equal volume.
+    ///
+    pub fn laplacian(&self) -> Vec<f64>
+    {
+        let particles = self.field.particles;
+
+        // The smoothing of the distance between particles, which keeps the
+        // exchange between particles that nearly coincide finite.
+        //
+        let epsilon = 0.01 * self.field.kernel.support_radius().powi(2);
+
+        maybe_par_iter!(0..self.quantities.len())
+            .map(|index|
+            {
+                self.field.neighbours_within(&particles.positions[index])
+                    .map(|(other, offset)|
+                    {
+                        let radius = offset.norm();
+                        let volume = particles.masses[other] / particles.densities[other];
+                        let derivative = self.field.kernel.influence_derivative(radius);
+
+                        2.0 * volume * (self.quantities[index] - self.quantities[other])
+                            * radius * derivative / (radius.powi(2) + epsilon)
+                    })
+                    .sum()
+            })
+            .collect()
+    }
 }
 
-pub struct UniformGradientField<const N: usize>
+/// Represents a field of gradients in N-dimensional space, sampled from the
+/// particles of a field.
+///
+/// ## Type Parameters
+///
+/// * `N`: The number of dimensions in the space (const generic parameter).
+/// * `K`: The type of the smoothing kernel.
+///
+/// ## Fields
+///
+/// * `field`     - The field of particles the gradients are sampled from.
+/// * `gradients` - The gradient at every particle.
+/// * `shepard`   - Whether interpolation is Shepard corrected.
+///
+pub struct UniformGradientField<'a, const N: usize, K: ?Sized + Kernel = dyn Kernel>
 {
-    kernel: FieldKernel<N>,
-    gradients: Vec<(FieldPos<N>,f64,[f64;N])>, // (position, density, gradient)
+    field: &'a UniformField<'a,N,K>,
+    gradients: Vec<[f64;N]>,
+    shepard: bool,
 }
 
-impl<const N: usize> UniformGradientField<N>
+impl<'a, const N: usize, K: ?Sized + Kernel> UniformGradientField<'a,N,K>
 {
+    /// Apply the Shepard correction when interpolating, dividing by the kernel
+    /// sum so constant gradients are reproduced exactly near free surfaces and
+    /// boundaries.
+    ///
+    pub fn shepard_corrected(mut self) -> Self
+    {
+        self.shepard = true;
+        self
+    }
+
     pub fn at(&self, position: FieldPos<N>) -> [f64;N]
     {
-        self.gradients.iter()
+        let particles = self.field.particles;
+
+        let gradient = self.field.neighbours.candidates(&position)
 
             // Calculate the euclidean distance from the desired position.
             //
-            .map(|(position_other, density, gradient)|
+            .map(|index|
             {
-                let radius = (position - position_other).map(f64::from).norm();
-                (radius, density, gradient)
+                let radius = (position - particles.positions[index]).map(f64::from).norm();
+                (radius, index)
             })
 
             // Filter only the gradients who are within the kernel's support
             // radius.
             //
-            .filter(|(radius, _density, _gradient)|
+            .filter(|(radius, _index)|
             {
-                *radius <= self.kernel.support_radius()
+                *radius <= self.field.kernel.support_radius()
             })
 
             // Calculate the influence this sample gradients has on the final
             // gradient.
             //
-            .map(|(radius, density, gradient)|
+            .map(|(radius, index)|
             {
-                let influence = self.kernel.influence(radius);
-                gradient.map(|q| q * influence / density)
+                let influence = self.field.kernel.influence(radius);
+                let volume = particles.masses[index] / particles.densities[index];
+                self.gradients[index].map(|q| q * influence * volume)
             })
 
             // Return the sum of all contributing gradients influences.
@@ -257,6 +551,119 @@ impl<const N: usize> UniformGradientField<N>
                 itertools::izip!(sum, grad)
                     .map(|(e_sum,e_grad)| e_sum + e_grad)
                     .to_array()
-            })
+            });
+
+        match self.shepard
+        {
+            true =>
+            {
+                let kernel_sum = self.field.kernel_sum(&position);
+                gradient.map(|q| shepard_correct(q, kernel_sum))
+            }
+            false => gradient,
+        }
+    }
+
+    /// Return the gradient at every particle.
+    ///
+    pub fn gradients(&self) -> &[[f64;N]]
+    {
+        &self.gradients
+    }
+}
+
+/// Divide an interpolated value by the kernel sum at its position, leaving
+/// positions outside the support of every particle at zero.
+///
+fn shepard_correct(value: f64, kernel_sum: f64) -> f64
+{
+    if kernel_sum > 0.0 { value / kernel_sum } else { 0.0 }
+}
+
+/// Return the renormalised gradient `g' = L g`, given the moment matrix
+/// `L⁻¹ = Σ m/ρ ∇W ⊗ (x_j - x_i)`, if the matrix is invertible.
+///
+/// The matrix is as small as the space, so it is inverted on the stack
+/// rather than factorised on the heap.
+///
+fn renormalise<const N: usize>(moment: &nalgebra::SMatrix<f64,N,N>, gradient: &FieldVec<N>) -> Option<FieldVec<N>>
+{
+    moment.try_inverse().map(|inverse| inverse * gradient)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::NORMALISATION_TOLERANCE;
+    use crate::kernels::WendlandC2;
+    use util::random::SplitMix64;
+
+    fn kernel() -> FieldKernel<2>
+    {
+        FieldKernel::new(WendlandC2, 3.0, NORMALISATION_TOLERANCE)
+    }
+
+    /// A jittered square of particles with up to date densities, whose edges
+    /// truncate the support of the particles near them.
+    ///
+    fn particles() -> ParticleSet<2>
+    {
+        let mut random = SplitMix64::new(11);
+        let mut jitter = || random.next_f32() * 0.2 - 0.1;
+
+        let positions = itertools::iproduct!(0..20, 0..20)
+            .map(|(i, j)| FieldPos::<2>::new(i as f32 + jitter(), j as f32 + jitter()))
+            .collect();
+
+        let mut particles = ParticleSet::from_positions(positions, 1.0);
+        particles.update_densities(&kernel());
+        particles
+    }
+
+    /// The greatest error of the gradient of a linear field at any particle.
+    ///
+    fn linear_gradient_error(correction: GradientCorrection) -> f64
+    {
+        let particles = particles();
+        let field = UniformField::new(kernel(), &particles);
+        let slope = FieldVec::<2>::new(2.0, -0.5);
+
+        let quantities = particles.positions.iter()
+            .map(|position| 1.0 + slope.dot(&position.map(f64::from)))
+            .collect::<Vec<_>>();
+
+        field.sample(quantities)
+            .kernel_gradient(correction)
+            .gradients()
+            .iter()
+            .map(|gradient| (FieldVec::<2>::from(*gradient) - slope).norm())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn renormalised_gradient_reproduces_linear_field()
+    {
+        assert!(linear_gradient_error(GradientCorrection::None) > 0.5);
+        assert!(linear_gradient_error(GradientCorrection::Renormalised) < 1e-6);
+    }
+
+    #[test]
+    fn shepard_correction_recovers_constant_field()
+    {
+        let particles = particles();
+        let field = UniformField::new(kernel(), &particles);
+        let quantities = vec![3.0; particles.len()];
+        let corrected = field.sample(&quantities).shepard_corrected();
+
+        // Beyond a corner, on an edge and in the middle of the square.
+        //
+        for position in [FieldPos::<2>::new(-1.0, -1.0), FieldPos::<2>::new(10.0, 0.0), FieldPos::<2>::new(10.0, 10.0)]
+        {
+            let quantity = corrected.at(position);
+            assert!((quantity - 3.0).abs() < 1e-12, "{} at {:?}", quantity, position);
+        }
+
+        assert!(field.sample(&quantities).at(FieldPos::<2>::new(-1.0, -1.0)) < 2.0);
     }
 }
@@ -0,0 +1,244 @@
+
+use std::io::{self, Read, Write};
+
+use crate::io::Frame;
+
+type FramePos<const N: usize> = nalgebra::SVector<f32,N>;
+
+/// The magic bytes every binary frame begins with.
+///
+pub const MAGIC: [u8;4] = *b"HYDF";
+
+/// The version of the binary frame format.
+///
+pub const VERSION: u32 = 1;
+
+/// Write a frame of particles in a compact little-endian binary format.
+///
+/// The format is a header followed by the columns of the frame;
+///
+/// | Field              | Type                                   |
+/// |--------------------|----------------------------------------|
+/// | magic              | `[u8;4]` = `HYDF`                      |
+/// | version            | `u32`                                  |
+/// | dimensions `N`     | `u32`                                  |
+/// | particle count `n` | `u64`                                  |
+/// | time               | `f64`                                  |
+/// | scalar columns `s` | `u32`                                  |
+/// | vector columns `v` | `u32`                                  |
+/// | column names       | `s + v` times a `u32` length and utf-8 |
+/// | positions          | `n * N` times `f32`                    |
+/// | scalar columns     | `s * n` times `f64`                    |
+/// | vector columns     | `v * n * N` times `f32`                |
+///
+pub fn write_binary<const N: usize>(frame: &Frame<N>, writer: impl Write) -> io::Result<()>
+{
+    let mut writer = io::BufWriter::new(writer);
+
+    // The header.
+    //
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(N as u32).to_le_bytes())?;
+    writer.write_all(&(frame.len() as u64).to_le_bytes())?;
+    writer.write_all(&frame.time.to_le_bytes())?;
+    writer.write_all(&(frame.scalars.len() as u32).to_le_bytes())?;
+    writer.write_all(&(frame.vectors.len() as u32).to_le_bytes())?;
+
+    let names = frame.scalars.iter().map(|(name, _)| name)
+        .chain(frame.vectors.iter().map(|(name, _)| name));
+
+    for name in names
+    {
+        writer.write_all(&(name.len() as u32).to_le_bytes())?;
+        writer.write_all(name.as_bytes())?;
+    }
+
+    // The columns.
+    //
+    let write_vectors = |writer: &mut io::BufWriter<_>, values: &[FramePos<N>]| -> io::Result<()>
+    {
+        for value in values.iter().flat_map(|value| value.iter())
+        {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    };
+
+    write_vectors(&mut writer, &frame.positions)?;
+    for (_, values) in &frame.scalars
+    {
+        for value in values
+        {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+    for (_, values) in &frame.vectors
+    {
+        write_vectors(&mut writer, values)?;
+    }
+
+    writer.flush()
+}
+
+/// Read a frame of particles written by [`write_binary`].
+///
+pub fn read_binary<const N: usize>(reader: impl Read) -> io::Result<Frame<N>>
+{
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut reader = LittleEndianReader(reader);
+
+    // The header.
+    //
+    if reader.bytes(4)? != MAGIC
+    {
+        return Err(invalid("not a binary frame".to_string()));
+    }
+
+    let version = reader.u32()?;
+    if version != VERSION
+    {
+        return Err(invalid(format!("unsupported binary frame version {}", version)));
+    }
+
+    let dimensions = reader.u32()? as usize;
+    if dimensions != N
+    {
+        return Err(invalid(format!("expected a {}-dimensional frame, found {} dimensions", N, dimensions)));
+    }
+
+    let count = reader.u64()? as usize;
+    let time = reader.f64()?;
+    let scalar_count = reader.u32()? as usize;
+    let vector_count = reader.u32()? as usize;
+
+    // The counts are untrusted, so nothing is allocated up front by them.
+    //
+    let mut names = Vec::new();
+    for _ in 0..scalar_count + vector_count
+    {
+        let length = reader.u32()? as usize;
+        let name = String::from_utf8(reader.bytes(length)?)
+            .map_err(|e| invalid(format!("column name is not utf-8: {}", e)))?;
+        names.push(name);
+    }
+
+    // The columns.
+    //
+    let mut frame = Frame::new(time, reader.vectors(count)?);
+
+    let mut names = names.into_iter();
+    for name in names.by_ref().take(scalar_count)
+    {
+        let values = (0..count)
+            .map(|_| reader.f64())
+            .collect::<io::Result<Vec<_>>>()?;
+
+        frame.add_scalar(name, values);
+    }
+    for name in names
+    {
+        frame.add_vector(name, reader.vectors(count)?);
+    }
+
+    Ok(frame)
+}
+
+struct LittleEndianReader<R: Read>(R);
+
+impl<R: Read> LittleEndianReader<R>
+{
+    /// Read a number of bytes, only allocating as many as the reader holds,
+    /// so an untrusted length can not exhaust memory.
+    ///
+    fn bytes(&mut self, count: usize) -> io::Result<Vec<u8>>
+    {
+        let mut bytes = Vec::new();
+        (&mut self.0).take(count as u64).read_to_end(&mut bytes)?;
+
+        match bytes.len() == count
+        {
+            true => Ok(bytes),
+            false => Err(io::Error::new(io::ErrorKind::InvalidData, "binary frame ends early")),
+        }
+    }
+
+    fn array<const B: usize>(&mut self) -> io::Result<[u8;B]>
+    {
+        let mut bytes = [0; B];
+        self.0.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32>
+    {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> io::Result<u64>
+    {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> io::Result<f32>
+    {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> io::Result<f64>
+    {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn vectors<const N: usize>(&mut self, count: usize) -> io::Result<Vec<FramePos<N>>>
+    {
+        (0..count)
+            .map(|_|
+            {
+                let mut vector = FramePos::<N>::zeros();
+                for k in 0..N
+                {
+                    vector[k] = self.f32()?;
+                }
+                Ok(vector)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn frame() -> Vec<u8>
+    {
+        let mut frame = Frame::<2>::new(0.5, vec![FramePos::<2>::new(1.0, 2.0)]);
+        frame.add_scalar("density", vec![1.0]);
+
+        let mut bytes = Vec::new();
+        write_binary(&frame, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn huge_name_length_is_invalid()
+    {
+        // Replace the length of the first column name with the largest u32.
+        //
+        let mut bytes = frame();
+        bytes[36..40].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let error = read_binary::<2>(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn frame_round_trips()
+    {
+        let frame = read_binary::<2>(frame().as_slice()).unwrap();
+
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame.scalar("density"), Some([1.0].as_slice()));
+    }
+}
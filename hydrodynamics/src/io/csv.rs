@@ -0,0 +1,172 @@
+
+use std::io::{self, BufRead, Write};
+
+use crate::io::Frame;
+
+type FramePos<const N: usize> = nalgebra::SVector<f32,N>;
+
+/// Return the name of the `k`th axis of a space.
+///
+fn axis_name(k: usize) -> String
+{
+    match k
+    {
+        0 => "x".to_string(),
+        1 => "y".to_string(),
+        2 => "z".to_string(),
+        k => format!("x{}", k),
+    }
+}
+
+/// Write a frame of particles as comma separated values, one row per particle.
+///
+/// The columns are the simulated time, then the position axes `x`, `y`, ...,
+/// then every scalar attribute, then the axes of every vector attribute as
+/// `<name>_x`, `<name>_y`, ...
+///
+pub fn write_csv<const N: usize>(frame: &Frame<N>, writer: impl Write) -> io::Result<()>
+{
+    let mut writer = io::BufWriter::new(writer);
+
+    // The header.
+    //
+    let header = std::iter::once("time".to_string())
+        .chain((0..N).map(axis_name))
+        .chain(frame.scalars.iter().map(|(name, _)| name.clone()))
+        .chain(frame.vectors.iter().flat_map(|(name, _)|
+        {
+            (0..N).map(move |k| format!("{}_{}", name, axis_name(k)))
+        }))
+        .collect::<Vec<_>>();
+
+    if let Some(name) = header.iter().find(|name| name.contains([',', '"', '\n']))
+    {
+        let message = format!("column name {:?} cannot be written as csv", name);
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+
+    writeln!(writer, "{}", header.join(","))?;
+
+    // A row per particle.
+    //
+    for i in 0..frame.len()
+    {
+        write!(writer, "{}", frame.time)?;
+        for value in frame.positions[i].iter()
+        {
+            write!(writer, ",{}", value)?;
+        }
+        for (_, values) in &frame.scalars
+        {
+            write!(writer, ",{}", values[i])?;
+        }
+        for (_, values) in &frame.vectors
+        {
+            for value in values[i].iter()
+            {
+                write!(writer, ",{}", value)?;
+            }
+        }
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Read a frame of particles written by [`write_csv`].
+///
+/// Consecutive columns named `<name>_x`, `<name>_y`, ... are read back as a
+/// vector attribute, and every other column as a scalar attribute.
+///
+pub fn read_csv<const N: usize>(reader: impl BufRead) -> io::Result<Frame<N>>
+{
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut lines = reader.lines();
+    let header = lines.next().ok_or_else(|| invalid("missing csv header".to_string()))??;
+    let header = header.split(',').map(str::to_string).collect::<Vec<_>>();
+
+    // Sort the columns into positions, scalars and vectors.
+    //
+    let position_names = std::iter::once("time".to_string())
+        .chain((0..N).map(axis_name))
+        .collect::<Vec<_>>();
+
+    if header.len() < position_names.len() || header[..position_names.len()] != position_names[..]
+    {
+        return Err(invalid(format!("expected csv columns to begin with {}", position_names.join(","))));
+    }
+
+    let mut scalar_columns = Vec::<(String, usize)>::new();
+    let mut vector_columns = Vec::<(String, usize)>::new();
+
+    let mut column = position_names.len();
+    while column < header.len()
+    {
+        let vector_name = header[column].strip_suffix("_x").filter(|name|
+        {
+            (0..N).all(|k| header.get(column + k) == Some(&format!("{}_{}", name, axis_name(k))))
+        });
+
+        match vector_name
+        {
+            Some(name) =>
+            {
+                vector_columns.push(( name.to_string(), column ));
+                column += N;
+            }
+            None =>
+            {
+                scalar_columns.push(( header[column].clone(), column ));
+                column += 1;
+            }
+        }
+    }
+
+    // Read the rows.
+    //
+    let mut time = 0.0;
+    let mut positions = Vec::<FramePos<N>>::new();
+    let mut scalars = vec![Vec::<f64>::new(); scalar_columns.len()];
+    let mut vectors = vec![Vec::<FramePos<N>>::new(); vector_columns.len()];
+
+    for (row, line) in lines.enumerate()
+    {
+        let line = line?;
+        if line.is_empty() { continue };
+
+        let values = line.split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>,_>>()
+            .map_err(|e| invalid(format!("csv row {}: {}", row + 1, e)))?;
+
+        if values.len() != header.len()
+        {
+            return Err(invalid(format!("csv row {} has {} columns, expected {}", row + 1, values.len(), header.len())));
+        }
+
+        let to_vector = |column: usize| FramePos::<N>::from_fn(|k,_| values[column + k] as f32);
+
+        time = values[0];
+        positions.push(to_vector(1));
+        for ((_, column), scalar) in itertools::izip!(&scalar_columns, &mut scalars)
+        {
+            scalar.push(values[*column]);
+        }
+        for ((_, column), vector) in itertools::izip!(&vector_columns, &mut vectors)
+        {
+            vector.push(to_vector(*column));
+        }
+    }
+
+    let mut frame = Frame::new(time, positions);
+    for ((name, _), values) in itertools::izip!(scalar_columns, scalars)
+    {
+        frame.add_scalar(name, values);
+    }
+    for ((name, _), values) in itertools::izip!(vector_columns, vectors)
+    {
+        frame.add_vector(name, values);
+    }
+    Ok(frame)
+}
@@ -0,0 +1,141 @@
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::io::Frame;
+use crate::io::binary::*;
+use crate::io::csv::*;
+
+/// The file format a frame of particles is dumped as.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpFormat
+{
+    /// A `.csv` file, see [`write_csv`].
+    Csv,
+    /// A `.bin` file, see [`write_binary`].
+    Binary,
+}
+
+impl DumpFormat
+{
+    /// Return the file extension of the format.
+    ///
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            DumpFormat::Csv => "csv",
+            DumpFormat::Binary => "bin",
+        }
+    }
+
+    /// Return the format of a file from its extension, if known.
+    ///
+    pub fn from_path(path: &Path) -> Option<Self>
+    {
+        match path.extension()?.to_str()?
+        {
+            "csv" => Some(DumpFormat::Csv),
+            "bin" => Some(DumpFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Write a frame of particles to a file in the given format.
+///
+pub fn write_dump<const N: usize>(frame: &Frame<N>, format: DumpFormat, path: impl AsRef<Path>) -> io::Result<()>
+{
+    let file = std::fs::File::create(path)?;
+
+    match format
+    {
+        DumpFormat::Csv => write_csv(frame, file),
+        DumpFormat::Binary => write_binary(frame, file),
+    }
+}
+
+/// Read a frame of particles from a file, in the format given by its
+/// extension.
+///
+pub fn read_dump<const N: usize>(path: impl AsRef<Path>) -> io::Result<Frame<N>>
+{
+    let path = path.as_ref();
+    let format = DumpFormat::from_path(path).ok_or_else(||
+    {
+        let message = format!("unknown dump format of {}", path.display());
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    })?;
+
+    let file = io::BufReader::new(std::fs::File::open(path)?);
+
+    match format
+    {
+        DumpFormat::Csv => read_csv(file),
+        DumpFormat::Binary => read_binary(file),
+    }
+}
+
+/// Writes a sequence of frames to a directory, one dump file per frame.
+///
+/// ## Fields
+///
+/// * `directory` - The directory the frames are written to.
+/// * `name`      - The prefix of every frame file.
+/// * `format`    - The format each frame is written as.
+/// * `frames`    - The number of frames written so far.
+///
+pub struct DumpSeries
+{
+    directory: PathBuf,
+    name: String,
+    format: DumpFormat,
+    frames: usize,
+}
+
+impl DumpSeries
+{
+    /// Create a new, empty sequence of frames in a directory, creating the
+    /// directory if needed.
+    ///
+    pub fn create(directory: impl AsRef<Path>, name: impl Into<String>, format: DumpFormat) -> io::Result<Self>
+    {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory,
+            name: name.into(),
+            format,
+            frames: 0,
+        })
+    }
+
+    /// Return the number of frames written so far.
+    ///
+    pub fn len(&self) -> usize
+    {
+        self.frames
+    }
+
+    /// Return whether no frames have been written yet.
+    ///
+    pub fn is_empty(&self) -> bool
+    {
+        self.frames == 0
+    }
+
+    /// Write the next frame of the sequence.
+    ///
+    pub fn write<const N: usize>(&mut self, frame: &Frame<N>) -> io::Result<PathBuf>
+    {
+        let file_name = format!("{}_{:06}.{}", self.name, self.frames, self.format.extension());
+        let file_path = self.directory.join(file_name);
+
+        write_dump(frame, self.format, &file_path)?;
+        self.frames += 1;
+
+        Ok(file_path)
+    }
+}
@@ -0,0 +1,171 @@
+
+use crate::ParticleSet;
+
+type FramePos<const N: usize> = nalgebra::SVector<f32,N>;
+
+/// Represents a snapshot of particles in N-dimensional space, along with any
+/// number of named per-particle attributes.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+///
+/// ## Fields
+///
+/// * `time`      - The simulated time of the snapshot.
+/// * `positions` - The position of every particle.
+/// * `scalars`   - Named scalar attribute columns, one value per particle.
+/// * `vectors`   - Named vector attribute columns, one value per particle.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame<const N: usize>
+{
+    pub time: f64,
+    pub positions: Vec<FramePos<N>>,
+    pub scalars: Vec<(String, Vec<f64>)>,
+    pub vectors: Vec<(String, Vec<FramePos<N>>)>,
+}
+
+impl<const N: usize> Frame<N>
+{
+    /// Create a new frame of particles without any attributes.
+    ///
+    pub fn new(time: f64, positions: Vec<FramePos<N>>) -> Self
+    {
+        Self {
+            time,
+            positions,
+            scalars: Vec::new(),
+            vectors: Vec::new(),
+        }
+    }
+
+    /// Return the number of particles in the frame.
+    ///
+    pub fn len(&self) -> usize
+    {
+        self.positions.len()
+    }
+
+    /// Return whether the frame has no particles.
+    ///
+    pub fn is_empty(&self) -> bool
+    {
+        self.positions.is_empty()
+    }
+
+    /// Add a named scalar attribute column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one value per particle.
+    ///
+    pub fn add_scalar(&mut self, name: impl Into<String>, values: Vec<f64>)
+    {
+        assert_eq!(values.len(), self.len(), "one scalar value per particle");
+        self.scalars.push(( name.into(), values ));
+    }
+
+    /// Add a named vector attribute column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one value per particle.
+    ///
+    pub fn add_vector(&mut self, name: impl Into<String>, values: Vec<FramePos<N>>)
+    {
+        assert_eq!(values.len(), self.len(), "one vector value per particle");
+        self.vectors.push(( name.into(), values ));
+    }
+
+    /// Return the named scalar attribute column, if present.
+    ///
+    pub fn scalar(&self, name: &str) -> Option<&[f64]>
+    {
+        self.scalars.iter()
+            .find(|(scalar_name, _)| scalar_name == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Return the named vector attribute column, if present.
+    ///
+    pub fn vector(&self, name: &str) -> Option<&[FramePos<N>]>
+    {
+        self.vectors.iter()
+            .find(|(vector_name, _)| vector_name == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Convert the frame to a particle set.
+    ///
+    /// The `velocity` vector column and the `mass`, `density`,
+    /// `support_radius`, `phase` and `temperature` scalar columns are read into
+    /// the properties of the particles, defaulting to stationary particles of
+    /// unit mass and zero temperature in the first phase, and every other
+    /// column is kept as an attribute column.
+    ///
+    pub fn to_particles(&self) -> ParticleSet<N>
+    {
+        let mut particles = ParticleSet::from_positions(self.positions.clone(), 1.0);
+
+        for (name, values) in &self.scalars
+        {
+            match name.as_str()
+            {
+                "mass" => particles.masses = values.clone(),
+                "density" => particles.densities = values.clone(),
+                "support_radius" => particles.support_radii = values.clone(),
+                "phase" => particles.phases = values.iter().map(|phase| *phase as usize).collect(),
+                "temperature" => particles.temperatures = values.clone(),
+                _ => particles.add_scalar(name.clone(), values.clone()),
+            }
+        }
+        for (name, values) in &self.vectors
+        {
+            match name.as_str()
+            {
+                "velocity" => particles.velocities = values.clone(),
+                _ => particles.add_vector(name.clone(), values.clone()),
+            }
+        }
+
+        particles
+    }
+
+    /// Create a frame of a particle set, with the `velocity` vector column and
+    /// the `mass` and `density` scalar columns before every attribute column,
+    /// a `support_radius` scalar column if any radius of support is set, a
+    /// `phase` scalar column if any particle is not in the first phase, and a
+    /// `temperature` scalar column if any particle is not at zero temperature.
+    ///
+    pub fn from_particles(time: f64, particles: &ParticleSet<N>) -> Self
+    {
+        let mut frame = Frame::new(time, particles.positions.clone());
+        frame.add_scalar("mass", particles.masses.clone());
+        frame.add_scalar("density", particles.densities.clone());
+        if particles.support_radii.iter().any(|radius| *radius != 0.0)
+        {
+            frame.add_scalar("support_radius", particles.support_radii.clone());
+        }
+        if particles.phases.iter().any(|phase| *phase != 0)
+        {
+            frame.add_scalar("phase", particles.phases.iter().map(|phase| *phase as f64).collect());
+        }
+        if particles.temperatures.iter().any(|temperature| *temperature != 0.0)
+        {
+            frame.add_scalar("temperature", particles.temperatures.clone());
+        }
+        frame.add_vector("velocity", particles.velocities.clone());
+
+        for (name, values) in &particles.scalars
+        {
+            frame.add_scalar(name.clone(), values.clone());
+        }
+        for (name, values) in &particles.vectors
+        {
+            frame.add_vector(name.clone(), values.clone());
+        }
+
+        frame
+    }
+}
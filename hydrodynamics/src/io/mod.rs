@@ -0,0 +1,12 @@
+
+mod frame;
+pub use frame::*;
+
+mod dump;
+pub use dump::*;
+
+pub mod vtk;
+
+pub mod csv;
+
+pub mod binary;
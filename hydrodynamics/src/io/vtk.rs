@@ -0,0 +1,255 @@
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::io::Frame;
+
+/// The VTK dataset a frame of particles is written as.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VtkFormat
+{
+    /// A `.vtp` file of vertices.
+    PolyData,
+    /// A `.vtu` file of vertex cells.
+    UnstructuredGrid,
+}
+
+impl VtkFormat
+{
+    /// Return the file extension of the format.
+    ///
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            VtkFormat::PolyData => "vtp",
+            VtkFormat::UnstructuredGrid => "vtu",
+        }
+    }
+}
+
+/// Write a frame of particles as a VTK PolyData (`.vtp`) file.
+///
+pub fn write_vtp<const N: usize>(frame: &Frame<N>, writer: impl Write) -> io::Result<()>
+{
+    write_frame(frame, VtkFormat::PolyData, writer)
+}
+
+/// Write a frame of particles as a VTK UnstructuredGrid (`.vtu`) file.
+///
+pub fn write_vtu<const N: usize>(frame: &Frame<N>, writer: impl Write) -> io::Result<()>
+{
+    write_frame(frame, VtkFormat::UnstructuredGrid, writer)
+}
+
+/// Write a frame of particles as a VTK file of the given format.
+///
+/// Every particle becomes a vertex, and every attribute column of the frame
+/// becomes a point data array of the same name. Positions and vectors are
+/// padded with zeros up to the three dimensions VTK expects.
+///
+/// https://docs.vtk.org/en/latest/design_documents/VTKFileFormats.html
+///
+pub fn write_frame<const N: usize>(frame: &Frame<N>, format: VtkFormat, writer: impl Write) -> io::Result<()>
+{
+    if N == 0 || N > 3
+    {
+        let message = format!("VTK cannot represent {}-dimensional particles", N);
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+
+    let mut writer = io::BufWriter::new(writer);
+    let count = frame.len();
+
+    let (dataset, piece) = match format
+    {
+        VtkFormat::PolyData => ("PolyData", format!(
+            r#"NumberOfPoints="{count}" NumberOfVerts="{count}" NumberOfLines="0" NumberOfStrips="0" NumberOfPolys="0""#)),
+        VtkFormat::UnstructuredGrid => ("UnstructuredGrid", format!(
+            r#"NumberOfPoints="{count}" NumberOfCells="{count}""#)),
+    };
+
+    writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+    writeln!(writer, r#"<VTKFile type="{dataset}" version="1.0" byte_order="LittleEndian" header_type="UInt64">"#)?;
+    writeln!(writer, r#"  <{dataset}>"#)?;
+    writeln!(writer, r#"    <Piece {piece}>"#)?;
+
+    // The particle positions.
+    //
+    writeln!(writer, r#"      <Points>"#)?;
+    write_vectors(&mut writer, None, &frame.positions)?;
+    writeln!(writer, r#"      </Points>"#)?;
+
+    // A vertex cell per particle.
+    //
+    let cells = match format
+    {
+        VtkFormat::PolyData => "Verts",
+        VtkFormat::UnstructuredGrid => "Cells",
+    };
+
+    writeln!(writer, r#"      <{cells}>"#)?;
+    write_array(&mut writer, "Int64", "connectivity", 0..count)?;
+    write_array(&mut writer, "Int64", "offsets", 1..=count)?;
+    if format == VtkFormat::UnstructuredGrid
+    {
+        const VTK_VERTEX: u8 = 1;
+        write_array(&mut writer, "UInt8", "types", std::iter::repeat_n(VTK_VERTEX, count))?;
+    }
+    writeln!(writer, r#"      </{cells}>"#)?;
+
+    // The particle attributes.
+    //
+    writeln!(writer, r#"      <PointData>"#)?;
+    for (name, values) in &frame.scalars
+    {
+        write_array(&mut writer, "Float64", name, values)?;
+    }
+    for (name, values) in &frame.vectors
+    {
+        write_vectors(&mut writer, Some(name), values)?;
+    }
+    writeln!(writer, r#"      </PointData>"#)?;
+
+    writeln!(writer, r#"    </Piece>"#)?;
+    writeln!(writer, r#"  </{dataset}>"#)?;
+    writeln!(writer, r#"</VTKFile>"#)?;
+
+    writer.flush()
+}
+
+fn write_array<T: std::fmt::Display>(
+    writer: &mut impl Write,
+    kind: &str,
+    name: &str,
+    values: impl IntoIterator<Item = T>,
+) -> io::Result<()>
+{
+    let name = escape(name);
+    write!(writer, r#"        <DataArray type="{kind}" Name="{name}" format="ascii">"#)?;
+    for value in values
+    {
+        write!(writer, " {}", value)?;
+    }
+    writeln!(writer, r#" </DataArray>"#)
+}
+
+fn write_vectors<const N: usize>(
+    writer: &mut impl Write,
+    name: Option<&str>,
+    values: &[nalgebra::SVector<f32,N>],
+) -> io::Result<()>
+{
+    let name = name.map(|name| format!(r#" Name="{}""#, escape(name))).unwrap_or_default();
+    write!(writer, r#"        <DataArray type="Float32"{name} NumberOfComponents="3" format="ascii">"#)?;
+    for value in values
+    {
+        for k in 0..3
+        {
+            write!(writer, " {}", if k < N { value[k] } else { 0.0 })?;
+        }
+    }
+    writeln!(writer, r#" </DataArray>"#)
+}
+
+fn escape(text: &str) -> String
+{
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes a time series of frames to a directory, one VTK file per frame,
+/// along with a `.pvd` collection indexing the frames by time.
+///
+/// The collection is rewritten after every frame, so ParaView can open a
+/// series even while it is still being written.
+///
+/// ## Fields
+///
+/// * `directory` - The directory the series is written to.
+/// * `name`      - The name of the collection, and prefix of every frame file.
+/// * `format`    - The VTK format each frame is written as.
+/// * `frames`    - The time and file name of every frame written so far.
+///
+pub struct VtkSeries
+{
+    directory: PathBuf,
+    name: String,
+    format: VtkFormat,
+    frames: Vec<(f64, String)>,
+}
+
+impl VtkSeries
+{
+    /// Create a new, empty time series in a directory, creating the directory
+    /// if needed.
+    ///
+    pub fn create(directory: impl AsRef<Path>, name: impl Into<String>, format: VtkFormat) -> io::Result<Self>
+    {
+        let directory = directory.as_ref().to_path_buf();
+        std::fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory,
+            name: name.into(),
+            format,
+            frames: Vec::new(),
+        })
+    }
+
+    /// Return the path of the `.pvd` collection.
+    ///
+    pub fn collection_path(&self) -> PathBuf
+    {
+        self.directory.join(format!("{}.pvd", self.name))
+    }
+
+    /// Return the number of frames written so far.
+    ///
+    pub fn len(&self) -> usize
+    {
+        self.frames.len()
+    }
+
+    /// Return whether no frames have been written yet.
+    ///
+    pub fn is_empty(&self) -> bool
+    {
+        self.frames.is_empty()
+    }
+
+    /// Write the next frame of the series, and update the collection.
+    ///
+    pub fn write<const N: usize>(&mut self, frame: &Frame<N>) -> io::Result<PathBuf>
+    {
+        let file_name = format!("{}_{:06}.{}", self.name, self.frames.len(), self.format.extension());
+        let file_path = self.directory.join(&file_name);
+
+        write_frame(frame, self.format, std::fs::File::create(&file_path)?)?;
+        self.frames.push(( frame.time, file_name ));
+
+        self.write_collection()?;
+        Ok(file_path)
+    }
+
+    fn write_collection(&self) -> io::Result<()>
+    {
+        let mut writer = io::BufWriter::new(std::fs::File::create(self.collection_path())?);
+
+        writeln!(writer, r#"<?xml version="1.0"?>"#)?;
+        writeln!(writer, r#"<VTKFile type="Collection" version="0.1" byte_order="LittleEndian">"#)?;
+        writeln!(writer, r#"  <Collection>"#)?;
+        for (time, file_name) in &self.frames
+        {
+            let file_name = escape(file_name);
+            writeln!(writer, r#"    <DataSet timestep="{time}" group="" part="0" file="{file_name}"/>"#)?;
+        }
+        writeln!(writer, r#"  </Collection>"#)?;
+        writeln!(writer, r#"</VTKFile>"#)?;
+
+        writer.flush()
+    }
+}
@@ -1,11 +1,16 @@
 
 use peroxide::fuga;
-use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::{Interpolation, KernelTable};
 
 /// Represents a symmetric smoothing kernel used in smoothed particle
 /// hydrodynamic simulations.
 ///
-pub trait Kernel
+/// Kernels are shared between fields, and between the threads evaluating a
+/// field, so must be both `Send` and `Sync`.
+///
+pub trait Kernel: Send + Sync
 {
     /// Defines the smoothing kernel used to calculate a property field for all
     /// distances `r` away from a particle.
@@ -20,73 +25,217 @@ pub trait Kernel
     /// To be a valid kernel, the kernel function for all `0 <= r <= h` must be;
     /// * Non-negative: `self.kernel(h, r) >= 0`
     /// * Symmetric: `self.kernel(h, r) == self.kernel(h, -r)`
-    /// * Compact: `self.kernel(h, 0) == 0`
+    /// * Compact: `self.kernel(h, h) == 0`
+    /// * Monotonic: `self.kernel(h, r)` does not increase with `r`
+    ///
+    /// These can be checked with [`crate::kernels::validate`].
     ///
     fn kernel(&self, h: f64, r: f64) -> f64;
+
+    /// Defines the derivative of the smoothing kernel with respect to the
+    /// distance `r`.
+    ///
+    /// Defaults to a central finite difference of the kernel, which kernels
+    /// with a closed-form derivative should override.
+    ///
+    /// # Arguments
+    ///
+    /// * `h` - The radius of support for the smoothing kernel.
+    /// * `r` - The distance between particle and field property.
+    ///
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        let delta = h * 1e-6;
+        (self.kernel(h, r + delta) - self.kernel(h, r - delta)) / (2.0 * delta)
+    }
+
+    /// Defines the coefficient normalising the smoothing kernel to integrate
+    /// to one over `n`-dimensional space, if known in closed form.
+    ///
+    /// Defaults to `None`, in which case the kernel is normalised by
+    /// integrating it numerically.
+    ///
+    /// # Arguments
+    ///
+    /// * `h` - The radius of support for the smoothing kernel.
+    /// * `n` - The number of dimensions in the space.
+    ///
+    fn normalisation(&self, _h: f64, _n: usize) -> Option<f64>
+    {
+        None
+    }
 }
 
+/// The relative error tolerance suitable for numerically normalising most
+/// kernels.
+///
+pub const NORMALISATION_TOLERANCE: f64 = 1e-9;
+
+/// The greatest depth the interval of support is bisected to when
+/// numerically normalising a kernel.
+///
+const NORMALISATION_DEPTH: u32 = 20;
+
 /// Represents a normalised field kernel used in smoothed particle hydrodynamic
 /// simulations.
 ///
 /// This struct encapsulates a symmetric kernel and provides methods to
 /// calculate the influence of particles on property fields.
 ///
+/// By default the kernel is called through `dyn Kernel`, so it can be chosen
+/// at runtime. Naming the kernel type instead, as in `FieldKernel<N, Poly6>`,
+/// dispatches statically so the kernel can be inlined into the loops over
+/// particle pairs.
+///
+/// The kernel can also be tabulated, trading a little accuracy for not
+/// evaluating the kernel for every particle pair.
+///
 /// ## Type Parameters
 ///
 /// * `N` - The number of dimensions in the space.
+/// * `K` - The type of the smoothing kernel.
 ///
-#[derive(Clone)]
-pub struct FieldKernel<const N: usize>
+pub struct FieldKernel<const N: usize, K: ?Sized + Kernel = dyn Kernel>
 {
     kernel_support_radius: f64,
     kernel_normalisation_coefficient: f64,
-    kernel: Rc<dyn Kernel>,
+    kernel: Arc<K>,
+    kernel_table: Option<Arc<KernelTable>>,
+}
+
+impl<const N: usize, K: ?Sized + Kernel> Clone for FieldKernel<N,K>
+{
+    fn clone(&self) -> Self
+    {
+        Self {
+            kernel_support_radius: self.kernel_support_radius,
+            kernel_normalisation_coefficient: self.kernel_normalisation_coefficient,
+            kernel: self.kernel.clone(),
+            kernel_table: self.kernel_table.clone(),
+        }
+    }
 }
 
 impl<const N: usize> FieldKernel<N>
 {
-    /// Creates a new `FieldKernel` instance with the specified parameters.
+    /// Creates a new `FieldKernel` instance with the specified parameters,
+    /// calling the kernel through dynamic dispatch.
     ///
     /// # Arguments
     ///
-    /// * `kernel`  - The smoothing kernel function to be used.
-    /// * `support` - The radius of support for the smoothing kernel.
-    /// * `steps`   - The number of discretization steps for the smoothing kernel.
+    /// * `kernel`    - The smoothing kernel function to be used.
+    /// * `support`   - The radius of support for the smoothing kernel.
+    /// * `tolerance` - The relative error tolerance when normalising the kernel
+    ///   numerically, see [`NORMALISATION_TOLERANCE`].
     ///
-    pub fn new<K>(kernel: K, support: f64, steps: usize) -> Self
+    pub fn new<K>(kernel: K, support: f64, tolerance: f64) -> Self
     where
         K: Kernel + 'static,
+    {
+        FieldKernel::<N,K>::new_static(kernel, support, tolerance).into_dyn()
+    }
+}
+
+impl<const N: usize, K: Kernel> FieldKernel<N,K>
+{
+    /// Creates a new `FieldKernel` instance with the specified parameters,
+    /// calling the kernel through static dispatch.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel`    - The smoothing kernel function to be used.
+    /// * `support`   - The radius of support for the smoothing kernel.
+    /// * `tolerance` - The relative error tolerance when normalising the kernel
+    ///   numerically, see [`NORMALISATION_TOLERANCE`].
+    ///
+    pub fn new_static(kernel: K, support: f64, tolerance: f64) -> Self
     {
         let mut field_kernel = Self
         {
             kernel_support_radius: support,
             kernel_normalisation_coefficient: 0.0,
-            kernel: Rc::new(kernel),
+            kernel: Arc::new(kernel),
+            kernel_table: None,
         };
 
-        field_kernel.normalise(steps);
+        field_kernel.normalise(tolerance);
         field_kernel
     }
 
-    /// Normalise the field kernel by integrating over the radius of support.
+    /// Convert the field kernel to call the kernel through dynamic dispatch.
+    ///
+    pub fn into_dyn(self) -> FieldKernel<N>
+    where
+        K: 'static,
+    {
+        FieldKernel {
+            kernel_support_radius: self.kernel_support_radius,
+            kernel_normalisation_coefficient: self.kernel_normalisation_coefficient,
+            kernel: self.kernel,
+            kernel_table: self.kernel_table,
+        }
+    }
+}
+
+impl<const N: usize, K: ?Sized + Kernel> FieldKernel<N,K>
+{
+    /// Normalise the field kernel, in closed form if the kernel provides one,
+    /// else by adaptively integrating over the radius of support.
     ///
     /// # Arguments
     ///
-    /// * `support` - The radius of support for the smoothing kernel.
-    /// * `steps`   - The number of discretization steps for any numerical methods.
+    /// * `tolerance` - The relative error tolerance for any numerical methods.
     ///
-    fn normalise(&mut self, steps: usize)
+    fn normalise(&mut self, tolerance: f64)
     {
         let support = self.kernel_support_radius;
 
-        let integrand = |r: f64| self.kernel.kernel(support,r) * util::nball::volume(N as u8,r);
+        if let Some(normal) = self.kernel.normalisation(support, N)
+        {
+            self.kernel_normalisation_coefficient = normal;
+            return;
+        }
+
+        // Integrate over every spherical shell of the support, each weighted by
+        // its surface area.
+        //
+        let integrand = |r: f64| self.kernel.kernel(support,r) * util::nball::surface_area(N as u8,r);
         let bounds = (0.0, support);
-        let integral = fuga::GaussLegendre(steps);
+        let integral = fuga::G7K15R(tolerance, NORMALISATION_DEPTH);
         let volume = fuga::integrate(integrand, bounds, integral);
 
         self.kernel_normalisation_coefficient = 1.0 / volume;
     }
 
+    /// Tabulate the normalised kernel and its derivative, so the influence is
+    /// interpolated from the table rather than evaluated.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples`       - The number of samples, spanning `0 <= r² <= h²`.
+    /// * `interpolation` - The interpolation between samples.
+    ///
+    pub fn tabulated(mut self, samples: usize, interpolation: Interpolation) -> Self
+    {
+        let table = KernelTable::new(
+            self.kernel.as_ref(),
+            self.kernel_support_radius,
+            self.kernel_normalisation_coefficient,
+            samples,
+            interpolation,
+        );
+
+        self.kernel_table = Some(Arc::new(table));
+        self
+    }
+
+    /// Return the table of the kernel, if tabulated.
+    ///
+    pub fn table(&self) -> Option<&KernelTable>
+    {
+        self.kernel_table.as_deref()
+    }
+
     /// Calculates the influence contribution to a property field by a particle
     /// at a distance `r`.
     ///
@@ -94,15 +243,74 @@ impl<const N: usize> FieldKernel<N>
     ///
     /// `r` - The distance to the particle.
     ///
+    #[inline]
     pub fn influence(&self, r: f64) -> f64
     {
         if r > self.kernel_support_radius { return 0.0 };
 
+        if let Some(table) = &self.kernel_table { return table.value(r) };
+
         let support = self.kernel_support_radius;
         let normal = self.kernel_normalisation_coefficient;
         self.kernel.kernel(support, r) * normal
     }
 
+    /// Calculates the derivative of the influence with respect to the distance
+    /// `r` to a particle.
+    ///
+    /// # Arguments
+    ///
+    /// `r` - The distance to the particle.
+    ///
+    #[inline]
+    pub fn influence_derivative(&self, r: f64) -> f64
+    {
+        if r > self.kernel_support_radius { return 0.0 };
+
+        if let Some(table) = &self.kernel_table { return table.derivative(r) };
+
+        let support = self.kernel_support_radius;
+        let normal = self.kernel_normalisation_coefficient;
+        self.kernel.derivative(support, r) * normal
+    }
+
+    /// Calculates the influence contribution to a property field by a particle
+    /// at a distance `r`, for a kernel with a different radius of support.
+    ///
+    /// The kernel is assumed to be self-similar, depending on `r` only through
+    /// `r / h` as every built-in kernel does, so the normalised kernel is
+    /// `W(r, h) = (h₀ / h)ᴺ W(r h₀ / h, h₀)` for the radius of support `h₀` of
+    /// the field kernel, and any table of the kernel is reused.
+    ///
+    /// # Arguments
+    ///
+    /// * `r`       - The distance to the particle.
+    /// * `support` - The radius of support for the smoothing kernel.
+    ///
+    #[inline]
+    pub fn influence_with_support(&self, r: f64, support: f64) -> f64
+    {
+        let scale = self.kernel_support_radius / support;
+        scale.powi(N as i32) * self.influence(r * scale)
+    }
+
+    /// Calculates the derivative of the influence with respect to the distance
+    /// `r` to a particle, for a kernel with a different radius of support.
+    ///
+    /// See [`FieldKernel::influence_with_support`].
+    ///
+    /// # Arguments
+    ///
+    /// * `r`       - The distance to the particle.
+    /// * `support` - The radius of support for the smoothing kernel.
+    ///
+    #[inline]
+    pub fn influence_derivative_with_support(&self, r: f64, support: f64) -> f64
+    {
+        let scale = self.kernel_support_radius / support;
+        scale.powi(N as i32 + 1) * self.influence_derivative(r * scale)
+    }
+
     /// Return the radius of support for the smoothing kernel.
     ///
     pub fn support_radius(&self) -> f64
@@ -110,3 +318,80 @@ impl<const N: usize> FieldKernel<N>
         self.kernel_support_radius
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::kernels::*;
+
+    /// Every built-in kernel, normalised over `N` dimensions.
+    ///
+    fn kernels<const N: usize>(support: f64) -> Vec<(&'static str, FieldKernel<N>)>
+    {
+        vec![
+            ("Poly6",         FieldKernel::new(Poly6,         support, NORMALISATION_TOLERANCE)),
+            ("DebrunSpiky",   FieldKernel::new(DebrunSpiky,   support, NORMALISATION_TOLERANCE)),
+            ("MullerViscous", FieldKernel::new(MullerViscous, support, NORMALISATION_TOLERANCE)),
+            ("CubicSpline",   FieldKernel::new(CubicSpline,   support, NORMALISATION_TOLERANCE)),
+            ("QuinticSpline", FieldKernel::new(QuinticSpline, support, NORMALISATION_TOLERANCE)),
+            ("WendlandC2",    FieldKernel::new(WendlandC2,    support, NORMALISATION_TOLERANCE)),
+            ("WendlandC4",    FieldKernel::new(WendlandC4,    support, NORMALISATION_TOLERANCE)),
+            ("WendlandC6",    FieldKernel::new(WendlandC6,    support, NORMALISATION_TOLERANCE)),
+        ]
+    }
+
+    /// Integrate the influence of a field kernel over `N` dimensional space by
+    /// the midpoint rule over spherical shells, independently of the
+    /// quadrature the kernel is normalised with.
+    ///
+    fn integral<const N: usize>(kernel: &FieldKernel<N>) -> f64
+    {
+        let shells = 100_000;
+        let width = kernel.support_radius() / shells as f64;
+
+        (0..shells)
+            .map(|shell|
+            {
+                let r = (shell as f64 + 0.5) * width;
+                kernel.influence(r) * util::nball::surface_area(N as u8, r) * width
+            })
+            .sum()
+    }
+
+    fn assert_normalised<const N: usize>()
+    {
+        for support in [0.25, 1.0, 3.0, 40.0]
+        {
+            for (name, kernel) in kernels::<N>(support)
+            {
+                // The `h / 2r` term of the viscous kernel can not be integrated
+                // along a line.
+                //
+                if N == 1 && name == "MullerViscous" { continue };
+
+                let integral = integral(&kernel);
+                assert!((integral - 1.0).abs() < 1e-4,
+                    "{} integrates to {} in {}D with support {}", name, integral, N, support);
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_are_normalised_in_1d()
+    {
+        assert_normalised::<1>();
+    }
+
+    #[test]
+    fn kernels_are_normalised_in_2d()
+    {
+        assert_normalised::<2>();
+    }
+
+    #[test]
+    fn kernels_are_normalised_in_3d()
+    {
+        assert_normalised::<3>();
+    }
+}
@@ -0,0 +1,33 @@
+
+use crate::Kernel;
+use crate::kernels::truncated_power::{self, TruncatedPower};
+
+/// The M4 cubic B-spline kernel, `(1 - q)³ - 4 (1/2 - q)³` with the second
+/// term vanishing beyond `q = r / h = 1/2`.
+///
+pub struct CubicSpline;
+
+const TERMS: [TruncatedPower;2] = [
+    TruncatedPower::new( 1.0, 1.0, 3, 0),
+    TruncatedPower::new(-4.0, 0.5, 3, 0),
+];
+
+impl Kernel for CubicSpline
+{
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::value(&TERMS, r.abs() / h)
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::derivative(&TERMS, r.abs() / h) / h * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        Some(truncated_power::normalisation(&TERMS, h, n))
+    }
+}
@@ -1,12 +1,32 @@
 
+use peroxide::fuga;
+
 use crate::Kernel;
 
 pub struct DebrunSpiky;
 
 impl Kernel for DebrunSpiky
 {
-    fn kernel(&self, _h: f64, _r: f64) -> f64
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
+    {
+        (h - r.abs()).powi(3)
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
     {
-        todo!("Implement the DebrunSpiky kernel");
+        -3.0 * (h - r.abs()).powi(2) * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        // ∫₀¹ (1 - u)³ uⁿ⁻¹ du = 6 Γ(n) / Γ(n + 4)
+        //
+        let n = n as f64;
+        let radial = 6.0 * fuga::gamma(n) / fuga::gamma(n + 4.0);
+        let sphere = util::nball::surface_area(n as u8, 1.0);
+
+        Some(1.0 / (sphere * radial * h.powf(n + 3.0)))
     }
 }
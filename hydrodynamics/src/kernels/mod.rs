@@ -7,3 +7,17 @@ pub use debrun_spiky::*;
 
 mod muller_viscous;
 pub use muller_viscous::*;
+
+mod cubic_spline;
+pub use cubic_spline::*;
+
+mod quintic_spline;
+pub use quintic_spline::*;
+
+mod wendland;
+pub use wendland::*;
+
+mod truncated_power;
+
+mod validate;
+pub use validate::*;
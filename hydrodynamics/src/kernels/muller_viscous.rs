@@ -5,8 +5,32 @@ pub struct MullerViscous;
 
 impl Kernel for MullerViscous
 {
-    fn kernel(&self, _h: f64, _r: f64) -> f64
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
     {
-        todo!("Implement the MullerViscous kernel");
+        let r = r.abs();
+        -r.powi(3) / (2.0 * h.powi(3)) + r.powi(2) / h.powi(2) + h / (2.0 * r) - 1.0
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        let derivative = -3.0 * r.powi(2) / (2.0 * h.powi(3)) + 2.0 * r.abs() / h.powi(2) - h / (2.0 * r.powi(2));
+        derivative * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        // The `h / 2r` term is only integrable in two or more dimensions.
+        //
+        if n < 2 { return None };
+
+        // ∫₀¹ (-u³/2 + u² + 1/2u - 1) uⁿ⁻¹ du
+        //
+        let n = n as f64;
+        let radial = -1.0 / (2.0 * (n + 3.0)) + 1.0 / (n + 2.0) + 1.0 / (2.0 * (n - 1.0)) - 1.0 / n;
+        let sphere = util::nball::surface_area(n as u8, 1.0);
+
+        Some(1.0 / (sphere * radial * h.powf(n)))
     }
 }
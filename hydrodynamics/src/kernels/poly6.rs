@@ -1,12 +1,32 @@
 
+use peroxide::fuga;
+
 use crate::Kernel;
 
 pub struct Poly6;
 
 impl Kernel for Poly6
 {
+    #[inline]
     fn kernel(&self, h: f64, r: f64) -> f64
     {
         (h.powi(2) - r.powi(2)).powi(3)
     }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        -6.0 * r * (h.powi(2) - r.powi(2)).powi(2)
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        // ∫₀¹ (1 - u²)³ uⁿ⁻¹ du = 3 Γ(n/2) / Γ(n/2 + 4)
+        //
+        let n = n as f64;
+        let radial = 3.0 * fuga::gamma(n / 2.0) / fuga::gamma(n / 2.0 + 4.0);
+        let sphere = util::nball::surface_area(n as u8, 1.0);
+
+        Some(1.0 / (sphere * radial * h.powf(n + 6.0)))
+    }
 }
@@ -0,0 +1,34 @@
+
+use crate::Kernel;
+use crate::kernels::truncated_power::{self, TruncatedPower};
+
+/// The M6 quintic B-spline kernel, `(1 - q)⁵ - 6 (2/3 - q)⁵ + 15 (1/3 - q)⁵`
+/// with each term vanishing beyond its root in `q = r / h`.
+///
+pub struct QuinticSpline;
+
+const TERMS: [TruncatedPower;3] = [
+    TruncatedPower::new(  1.0, 1.0,       5, 0),
+    TruncatedPower::new( -6.0, 2.0 / 3.0, 5, 0),
+    TruncatedPower::new( 15.0, 1.0 / 3.0, 5, 0),
+];
+
+impl Kernel for QuinticSpline
+{
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::value(&TERMS, r.abs() / h)
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::derivative(&TERMS, r.abs() / h) / h * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        Some(truncated_power::normalisation(&TERMS, h, n))
+    }
+}
@@ -0,0 +1,90 @@
+
+use peroxide::fuga;
+
+/// Represents a term `c (a - q)₊ᵏ qᵐ` of a kernel written as a sum of
+/// truncated powers of the distance `q = r / h` relative to the support.
+///
+/// Every polynomial and piecewise polynomial kernel can be written as such a
+/// sum, which can be evaluated, differentiated and integrated exactly.
+///
+/// ## Fields
+///
+/// * `coefficient` - The coefficient `c` of the term.
+/// * `root`        - The distance `a` beyond which the term is zero.
+/// * `power`       - The power `k` of the truncated factor.
+/// * `degree`      - The power `m` of the distance.
+///
+pub(crate) struct TruncatedPower
+{
+    pub coefficient: f64,
+    pub root: f64,
+    pub power: i32,
+    pub degree: i32,
+}
+
+impl TruncatedPower
+{
+    pub const fn new(coefficient: f64, root: f64, power: i32, degree: i32) -> Self
+    {
+        Self { coefficient, root, power, degree }
+    }
+}
+
+/// Evaluate a sum of truncated powers at a distance `q`.
+///
+pub(crate) fn value(terms: &[TruncatedPower], q: f64) -> f64
+{
+    terms.iter()
+        .filter(|term| q < term.root)
+        .map(|term| term.coefficient * (term.root - q).powi(term.power) * q.powi(term.degree))
+        .sum()
+}
+
+/// Evaluate the derivative of a sum of truncated powers at a distance `q`.
+///
+pub(crate) fn derivative(terms: &[TruncatedPower], q: f64) -> f64
+{
+    terms.iter()
+        .filter(|term| q < term.root)
+        .map(|term|
+        {
+            let truncated = (term.root - q).powi(term.power);
+            let truncated_derivative = -term.power as f64 * (term.root - q).powi(term.power - 1);
+
+            let distance = q.powi(term.degree);
+            let distance_derivative = match term.degree
+            {
+                0 => 0.0,
+                m => m as f64 * q.powi(m - 1),
+            };
+
+            term.coefficient * (truncated_derivative * distance + truncated * distance_derivative)
+        })
+        .sum()
+}
+
+/// Return the coefficient normalising a kernel, written as a sum of truncated
+/// powers of `q = r / h`, to integrate to one over `n`-dimensional space.
+///
+/// Each term integrates exactly against the surface area of the sphere of
+/// radius `q` as a beta function;
+///
+/// `∫₀ᵃ (a - q)ᵏ qᵐ⁺ⁿ⁻¹ dq = aᵏ⁺ᵐ⁺ⁿ Γ(k + 1) Γ(m + n) / Γ(k + m + n + 1)`
+///
+pub(crate) fn normalisation(terms: &[TruncatedPower], h: f64, n: usize) -> f64
+{
+    let moment = terms.iter()
+        .map(|term|
+        {
+            let k = term.power as f64;
+            let m = term.degree as f64;
+            let n = n as f64;
+
+            term.coefficient * term.root.powf(k + m + n)
+                * fuga::gamma(k + 1.0) * fuga::gamma(m + n) / fuga::gamma(k + m + n + 1.0)
+        })
+        .sum::<f64>();
+
+    let sphere = util::nball::surface_area(n as u8, 1.0);
+    1.0 / (sphere * moment * h.powi(n as i32))
+}
@@ -0,0 +1,247 @@
+
+use std::fmt;
+
+use peroxide::fuga;
+
+use crate::Kernel;
+
+/// The conditions a kernel is validated under.
+///
+/// ## Fields
+///
+/// * `support`    - The radius of support the kernel is evaluated with.
+/// * `dimensions` - The number of dimensions the kernel is normalised in.
+/// * `samples`    - The number of intervals the support is sampled over.
+/// * `tolerance`  - The relative tolerance of the derivative and
+///   normalisation checks.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Validation
+{
+    pub support: f64,
+    pub dimensions: usize,
+    pub samples: usize,
+    pub tolerance: f64,
+}
+
+impl Default for Validation
+{
+    fn default() -> Self
+    {
+        Self {
+            support: 1.0,
+            dimensions: 3,
+            samples: 1000,
+            tolerance: 1e-4,
+        }
+    }
+}
+
+/// A property a kernel violates.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Violation
+{
+    /// The kernel is negative at a distance within its support.
+    Negative { r: f64, value: f64 },
+    /// The kernel differs between a distance and its negation.
+    Asymmetric { r: f64, value: f64, mirrored: f64 },
+    /// The kernel is not zero at the radius of support.
+    NonZeroAtSupport { value: f64 },
+    /// The kernel increases with distance.
+    NonMonotonic { r: f64, value: f64, previous: f64 },
+    /// The kernel does not have a finite, positive integral over space.
+    Unnormalisable { integral: f64 },
+    /// The closed-form normalisation disagrees with numeric integration.
+    NormalisationMismatch { closed_form: f64, numeric: f64 },
+    /// The derivative disagrees with a finite difference of the kernel.
+    DerivativeMismatch { r: f64, derivative: f64, finite_difference: f64 },
+}
+
+impl fmt::Display for Violation
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Violation::Negative { r, value } =>
+                write!(f, "negative value {} at r = {}", value, r),
+            Violation::Asymmetric { r, value, mirrored } =>
+                write!(f, "value {} at r = {} but {} at r = {}", value, r, mirrored, -r),
+            Violation::NonZeroAtSupport { value } =>
+                write!(f, "non-zero value {} at r = h", value),
+            Violation::NonMonotonic { r, value, previous } =>
+                write!(f, "value increases from {} to {} at r = {}", previous, value, r),
+            Violation::Unnormalisable { integral } =>
+                write!(f, "cannot be normalised, integrates to {}", integral),
+            Violation::NormalisationMismatch { closed_form, numeric } =>
+                write!(f, "closed-form normalisation {} but numerically {}", closed_form, numeric),
+            Violation::DerivativeMismatch { r, derivative, finite_difference } =>
+                write!(f, "derivative {} at r = {} but finite difference {}", derivative, r, finite_difference),
+        }
+    }
+}
+
+/// Sample a kernel over its support and report every violation of the
+/// properties documented by [`Kernel`].
+///
+/// The kernel is unnormalisable if its integral over space is not finite and
+/// positive, or does not converge towards `r = 0`, as for a kernel with a
+/// `1/r` singularity in one dimension.
+///
+pub fn validate<K: ?Sized + Kernel>(kernel: &K, validation: &Validation) -> Vec<Violation>
+{
+    let h = validation.support;
+    let tolerance = validation.tolerance;
+    let radius = |i: usize| h * i as f64 / validation.samples as f64;
+
+    let mut violations = Vec::new();
+
+    // The value of the kernel.
+    //
+    let mut previous = f64::INFINITY;
+    for r in (0..=validation.samples).map(radius)
+    {
+        let value = kernel.kernel(h, r);
+        let mirrored = kernel.kernel(h, -r);
+
+        if value < 0.0
+        {
+            violations.push(Violation::Negative { r, value });
+        }
+        if value != mirrored
+        {
+            violations.push(Violation::Asymmetric { r, value, mirrored });
+        }
+        if value > previous
+        {
+            violations.push(Violation::NonMonotonic { r, value, previous });
+        }
+        previous = value;
+    }
+
+    let value = kernel.kernel(h, h);
+    if value.abs() > tolerance * kernel.kernel(h, radius(1)).abs()
+    {
+        violations.push(Violation::NonZeroAtSupport { value });
+    }
+
+    // The derivative of the kernel, within the support where the kernel is
+    // smooth.
+    //
+    let delta = h * 1e-6;
+    let derivatives = (1..validation.samples)
+        .map(radius)
+        .map(|r|
+        {
+            let finite_difference = (kernel.kernel(h, r + delta) - kernel.kernel(h, r - delta)) / (2.0 * delta);
+            (r, kernel.derivative(h, r), finite_difference)
+        })
+        .collect::<Vec<_>>();
+
+    let scale = derivatives.iter()
+        .map(|(_r, _derivative, finite_difference)| finite_difference.abs())
+        .fold(0.0, f64::max);
+
+    for (r, derivative, finite_difference) in derivatives
+    {
+        if (derivative - finite_difference).abs() > tolerance * scale
+        {
+            violations.push(Violation::DerivativeMismatch { r, derivative, finite_difference });
+        }
+    }
+
+    // The normalisation of the kernel.
+    //
+    let n = validation.dimensions as u8;
+    let integrand = |r: f64| kernel.kernel(h, r) * util::nball::surface_area(n, r);
+    let integrate = |bounds| fuga::integrate(integrand, bounds, fuga::G7K15R(1e-10, 30));
+
+    let inner = integrate((h * 1e-8, h * 1e-4));
+    let integral = inner + integrate((h * 1e-4, h));
+
+    if !integral.is_finite() || integral <= 0.0 || inner.abs() > 1e-2 * integral.abs()
+    {
+        violations.push(Violation::Unnormalisable { integral });
+    }
+    else if let Some(closed_form) = kernel.normalisation(h, validation.dimensions)
+    {
+        let numeric = 1.0 / integral;
+        if (closed_form - numeric).abs() > tolerance * numeric
+        {
+            violations.push(Violation::NormalisationMismatch { closed_form, numeric });
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::kernels::*;
+
+    fn assert_valid(name: &str, kernel: &dyn Kernel)
+    {
+        for dimensions in [2, 3]
+        {
+            let violations = validate(kernel, &Validation { dimensions, ..Validation::default() });
+
+            assert!(violations.is_empty(), "{} in {}D: {}", name, dimensions,
+                violations.iter().map(Violation::to_string).collect::<Vec<_>>().join(", "));
+        }
+    }
+
+    #[test]
+    fn built_in_kernels_are_valid()
+    {
+        assert_valid("Poly6", &Poly6);
+        assert_valid("DebrunSpiky", &DebrunSpiky);
+        assert_valid("MullerViscous", &MullerViscous);
+        assert_valid("CubicSpline", &CubicSpline);
+        assert_valid("QuinticSpline", &QuinticSpline);
+        assert_valid("WendlandC2", &WendlandC2);
+        assert_valid("WendlandC4", &WendlandC4);
+        assert_valid("WendlandC6", &WendlandC6);
+    }
+
+    /// A cone falling through zero halfway across its support, so it is
+    /// negative and integrates to less than zero in 3D, with the sign of its
+    /// derivative wrong.
+    ///
+    struct Broken;
+
+    impl Kernel for Broken
+    {
+        fn kernel(&self, h: f64, r: f64) -> f64
+        {
+            1.0 - 2.0 * r.abs() / h
+        }
+
+        fn derivative(&self, h: f64, _r: f64) -> f64
+        {
+            2.0 / h
+        }
+    }
+
+    #[test]
+    fn broken_kernel_is_reported()
+    {
+        let violations = validate(&Broken, &Validation::default());
+
+        assert!(violations.iter().any(|v| matches!(v, Violation::Negative { .. })));
+        assert!(violations.iter().any(|v| matches!(v, Violation::NonZeroAtSupport { .. })));
+        assert!(violations.iter().any(|v| matches!(v, Violation::DerivativeMismatch { .. })));
+        assert!(violations.iter().any(|v| matches!(v, Violation::Unnormalisable { .. })));
+        assert!(violations.iter().all(|v| !matches!(v, Violation::Asymmetric { .. } | Violation::NonMonotonic { .. })));
+    }
+
+    #[test]
+    fn singular_kernel_is_unnormalisable_in_1d()
+    {
+        let violations = validate(&MullerViscous, &Validation { dimensions: 1, ..Validation::default() });
+
+        assert!(violations.iter().any(|v| matches!(v, Violation::Unnormalisable { .. })));
+    }
+}
@@ -0,0 +1,97 @@
+
+
+use crate::Kernel;
+use crate::kernels::truncated_power::{self, TruncatedPower};
+
+/// The Wendland C2 kernel, `(1 - q)⁴ (1 + 4q)` in `q = r / h`, positive definite
+/// in up to three dimensions.
+///
+pub struct WendlandC2;
+
+const C2_TERMS: [TruncatedPower;2] = [
+    TruncatedPower::new(1.0, 1.0, 4, 0),
+    TruncatedPower::new(4.0, 1.0, 4, 1),
+];
+
+impl Kernel for WendlandC2
+{
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::value(&C2_TERMS, r.abs() / h)
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::derivative(&C2_TERMS, r.abs() / h) / h * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        Some(truncated_power::normalisation(&C2_TERMS, h, n))
+    }
+}
+
+/// The Wendland C4 kernel, `(1 - q)⁶ (1 + 6q + 35q²/3)` in `q = r / h`,
+/// positive definite in up to three dimensions.
+///
+pub struct WendlandC4;
+
+const C4_TERMS: [TruncatedPower;3] = [
+    TruncatedPower::new(       1.0, 1.0, 6, 0),
+    TruncatedPower::new(       6.0, 1.0, 6, 1),
+    TruncatedPower::new(35.0 / 3.0, 1.0, 6, 2),
+];
+
+impl Kernel for WendlandC4
+{
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::value(&C4_TERMS, r.abs() / h)
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::derivative(&C4_TERMS, r.abs() / h) / h * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        Some(truncated_power::normalisation(&C4_TERMS, h, n))
+    }
+}
+
+/// The Wendland C6 kernel, `(1 - q)⁸ (1 + 8q + 25q² + 32q³)` in `q = r / h`,
+/// positive definite in up to three dimensions.
+///
+pub struct WendlandC6;
+
+const C6_TERMS: [TruncatedPower;4] = [
+    TruncatedPower::new( 1.0, 1.0, 8, 0),
+    TruncatedPower::new( 8.0, 1.0, 8, 1),
+    TruncatedPower::new(25.0, 1.0, 8, 2),
+    TruncatedPower::new(32.0, 1.0, 8, 3),
+];
+
+impl Kernel for WendlandC6
+{
+    #[inline]
+    fn kernel(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::value(&C6_TERMS, r.abs() / h)
+    }
+
+    #[inline]
+    fn derivative(&self, h: f64, r: f64) -> f64
+    {
+        truncated_power::derivative(&C6_TERMS, r.abs() / h) / h * r.signum()
+    }
+
+    fn normalisation(&self, h: f64, n: usize) -> Option<f64>
+    {
+        Some(truncated_power::normalisation(&C6_TERMS, h, n))
+    }
+}
@@ -1,8 +1,29 @@
+#[macro_use]
+mod parallel;
 
 mod kernel;
 pub use kernel::*;
 
+mod table;
+pub use table::*;
+
 pub mod kernels;
 
+mod particles;
+pub use particles::*;
+
 mod field;
 pub use field::*;
+
+mod adaptive;
+pub use adaptive::*;
+
+mod neighbours;
+pub use neighbours::*;
+
+mod raster;
+pub use raster::*;
+
+pub mod solver;
+
+pub mod io;
@@ -0,0 +1,91 @@
+
+use std::collections::HashMap;
+
+type CellPos<const N: usize> = nalgebra::SVector<f32,N>;
+
+/// Represents a uniform grid of cells used to find the particles near a
+/// position without visiting every particle.
+///
+/// Particles are bucketed into cubic cells the size of the search radius, so
+/// every particle within the search radius of a position lies in the cell of
+/// that position or one of the cells adjacent to it.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+///
+/// ## Fields
+///
+/// * `cell_size` - The width of every cell, at least the search radius.
+/// * `cells`     - The index of every particle within each occupied cell.
+///
+#[derive(Clone, Debug)]
+pub struct NeighbourGrid<const N: usize>
+{
+    cell_size: f64,
+    cells: HashMap<[i64;N],Vec<usize>>,
+}
+
+impl<const N: usize> NeighbourGrid<N>
+{
+    /// Create a new, empty neighbour grid for a search radius.
+    ///
+    pub fn new(radius: f64) -> Self
+    {
+        Self {
+            cell_size: radius,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Return the width of every cell.
+    ///
+    pub fn cell_size(&self) -> f64
+    {
+        self.cell_size
+    }
+
+    /// Return the cell containing a position.
+    ///
+    fn cell(&self, position: &CellPos<N>) -> [i64;N]
+    {
+        std::array::from_fn(|k| (position[k] as f64 / self.cell_size).floor() as i64)
+    }
+
+    /// Insert the particle with an index at a position.
+    ///
+    pub fn insert(&mut self, index: usize, position: &CellPos<N>)
+    {
+        self.cells.entry(self.cell(position)).or_default().push(index);
+    }
+
+    /// Return the index of every particle that may lie within the search
+    /// radius of a position.
+    ///
+    /// Candidates are visited in a fixed order, cell by cell, and in order of
+    /// insertion within a cell, so sums over the candidates are reproducible.
+    ///
+    pub fn candidates(&self, position: &CellPos<N>) -> impl Iterator<Item = usize> + '_
+    {
+        let cell = self.cell(position);
+
+        (0..3usize.pow(N as u32))
+
+            // Visit the cell of the position, and every cell adjacent to it.
+            //
+            .map(move |offset|
+            {
+                std::array::from_fn(|k|
+                {
+                    let offset_k = (offset / 3usize.pow(k as u32)) % 3;
+                    cell[k] + offset_k as i64 - 1
+                })
+            })
+
+            // Visit every particle within those cells.
+            //
+            .filter_map(|neighbour_cell: [i64;N]| self.cells.get(&neighbour_cell))
+            .flatten()
+            .copied()
+    }
+}
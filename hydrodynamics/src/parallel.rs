@@ -0,0 +1,175 @@
+
+/// Iterate over a collection, across threads when the `parallel` feature is
+/// enabled and in order otherwise.
+///
+/// Both iterators yield the same items to the same closures, and collect them
+/// in the same order, so the result of an evaluation does not depend on the
+/// feature. Users of the parallel iterator must bring `rayon::prelude::*` into
+/// scope under the feature.
+///
+macro_rules! maybe_par_iter
+{
+    ($collection:expr) =>
+    {{
+        #[cfg(feature = "parallel")]
+        let iter = rayon::iter::IntoParallelIterator::into_par_iter($collection);
+        #[cfg(not(feature = "parallel"))]
+        let iter = IntoIterator::into_iter($collection);
+        iter
+    }};
+}
+
+#[cfg(test)]
+mod tests
+{
+    use crate::*;
+    use crate::kernels::WendlandC2;
+    use crate::solver::*;
+    use util::random::SplitMix64;
+
+    type FieldPos<const N: usize> = nalgebra::SVector<f32,N>;
+    type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+    /// Run a closure on a single thread, giving the serial reference of an
+    /// evaluation under the `parallel` feature.
+    ///
+    fn serially<T: Send>(evaluate: impl FnOnce() -> T + Send) -> T
+    {
+        #[cfg(feature = "parallel")]
+        let result = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(evaluate);
+        #[cfg(not(feature = "parallel"))]
+        let result = evaluate();
+        result
+    }
+
+    fn kernel() -> FieldKernel<2>
+    {
+        FieldKernel::new(WendlandC2, 3.0, NORMALISATION_TOLERANCE)
+    }
+
+    /// A jittered block of moving particles, large enough to be split across
+    /// threads, with up to date densities.
+    ///
+    fn particles() -> ParticleSet<2>
+    {
+        let mut random = SplitMix64::new(7);
+        let mut jitter = || random.next_f32() * 0.2 - 0.1;
+
+        let positions = itertools::iproduct!(0..40, 0..30)
+            .map(|(i, j)| FieldPos::<2>::new(i as f32 + jitter(), j as f32 + jitter()))
+            .collect::<Vec<_>>();
+
+        let mut particles = ParticleSet::from_positions(positions, 1.0);
+        particles.velocities = (0..particles.len())
+            .map(|_| FieldPos::<2>::new(jitter(), jitter()))
+            .collect();
+        particles.update_densities(&kernel());
+
+        particles
+    }
+
+    /// A quantity varying nonlinearly over the block.
+    ///
+    fn quantities(particles: &ParticleSet<2>) -> Vec<f64>
+    {
+        particles.positions.iter()
+            .map(|position| (position.x as f64).powi(2) + 3.0 * position.y as f64)
+            .collect()
+    }
+
+    #[test]
+    fn densities_match_serial()
+    {
+        let particles = particles();
+        let field = UniformField::new(kernel(), &particles);
+
+        let reference = particles.positions.iter()
+            .map(|position| field.density(position))
+            .collect::<Vec<_>>();
+
+        assert_eq!(field.densities(), reference);
+    }
+
+    #[test]
+    fn sampled_gradients_match_serial()
+    {
+        let particles = particles();
+        let field = UniformField::new(kernel(), &particles);
+        let quantities = quantities(&particles);
+        let sampled = field.sample(&quantities);
+        let delta = 1e-2;
+
+        let reference = itertools::izip!(&particles.positions, &quantities)
+            .map(|(position, quantity)|
+            {
+                std::array::from_fn(|i|
+                {
+                    let offset = FieldPos::<2>::from_fn(|k, _| if i == k { delta as f32 } else { 0.0 });
+                    (sampled.at(position + offset) - quantity) / delta
+                })
+            })
+            .collect::<Vec<[f64;2]>>();
+
+        assert_eq!(sampled.gradient(delta).gradients(), reference.as_slice());
+    }
+
+    #[test]
+    fn kernel_gradients_match_serial()
+    {
+        let particles = particles();
+        let field = UniformField::new(kernel(), &particles);
+        let quantities = quantities(&particles);
+
+        let reference = (0..particles.len())
+            .map(|index|
+            {
+                field.neighbours_within(&particles.positions[index])
+                    .map(|(other, offset)|
+                    {
+                        let volume = particles.masses[other] / particles.densities[other];
+                        field.influence_gradient(&offset) * (volume * (quantities[other] - quantities[index]))
+                    })
+                    .fold(FieldVec::<2>::zeros(), |gradient, term| gradient + term)
+                    .into()
+            })
+            .collect::<Vec<[f64;2]>>();
+
+        let gradients = field.sample(&quantities).kernel_gradient(GradientCorrection::None);
+        assert_eq!(gradients.gradients(), reference.as_slice());
+
+        let renormalised = |field: &UniformField<2>| field.sample(&quantities)
+            .kernel_gradient(GradientCorrection::Renormalised)
+            .gradients()
+            .to_vec();
+        assert_eq!(renormalised(&field), serially(|| renormalised(&field)));
+    }
+
+    #[test]
+    fn solver_accelerations_match_serial()
+    {
+        let particles = particles();
+        let kernel = kernel();
+
+        let mut phase = Phase::new(EquationOfState::Tait { rest_density: 1.0, stiffness: 10.0, exponent: 7.0 });
+        phase.viscosity = 0.1;
+
+        let mut solver = Solver::new(kernel.clone(), vec![phase]);
+        solver.surface_tension = SurfaceTension::Akinci { coefficient: 0.5 };
+        solver.artificial_viscosity = ArtificialViscosity { alpha: 0.1, beta: 0.2, balsara: true };
+        solver.colliders.push(Collider::polygon(&[
+            FieldPos::<2>::new(-2.0, -2.0),
+            FieldPos::<2>::new(42.0, -2.0),
+            FieldPos::<2>::new(42.0, 32.0),
+            FieldPos::<2>::new(-2.0, 32.0),
+        ], 0.5, &kernel));
+
+        let accelerations = solver.accelerations(&particles);
+
+        assert_eq!(accelerations.len(), particles.len());
+        assert_eq!(accelerations, serially(|| solver.accelerations(&particles)));
+    }
+}
@@ -0,0 +1,333 @@
+
+use crate::{AdaptiveField, FieldKernel, Kernel, UniformField};
+
+type ParticlePos<const N: usize> = nalgebra::SVector<f32,N>;
+
+/// Represents a set of particles in N-dimensional space, stored as a structure
+/// of arrays so each property of every particle is contiguous in memory.
+///
+/// Fields borrow the particle set rather than copying from it, so any number
+/// of quantities can be sampled from the same particles.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+///
+/// ## Fields
+///
+/// * `positions`     - The position of every particle.
+/// * `velocities`    - The velocity of every particle.
+/// * `masses`        - The mass of every particle.
+/// * `densities`     - The density at every particle.
+/// * `support_radii` - The radius of support of the smoothing kernel of every
+///   particle, used by adaptive fields and zero until set.
+/// * `phases`        - The index of the phase of every particle into the phases
+///   of a solver, zero for a fluid of a single phase.
+/// * `temperatures`  - The temperature of every particle.
+/// * `scalars`       - Named scalar attribute columns, one value per particle.
+/// * `vectors`       - Named vector attribute columns, one value per particle.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParticleSet<const N: usize>
+{
+    pub positions: Vec<ParticlePos<N>>,
+    pub velocities: Vec<ParticlePos<N>>,
+    pub masses: Vec<f64>,
+    pub densities: Vec<f64>,
+    pub support_radii: Vec<f64>,
+    pub phases: Vec<usize>,
+    pub temperatures: Vec<f64>,
+    pub scalars: Vec<(String, Vec<f64>)>,
+    pub vectors: Vec<(String, Vec<ParticlePos<N>>)>,
+}
+
+impl<const N: usize> ParticleSet<N>
+{
+    /// Create a new, empty particle set.
+    ///
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Create a new particle set of stationary particles of equal mass.
+    ///
+    pub fn from_positions(positions: Vec<ParticlePos<N>>, mass: f64) -> Self
+    {
+        let count = positions.len();
+
+        Self {
+            positions,
+            velocities: vec![ParticlePos::zeros(); count],
+            masses: vec![mass; count],
+            densities: vec![0.0; count],
+            support_radii: vec![0.0; count],
+            phases: vec![0; count],
+            temperatures: vec![0.0; count],
+            scalars: Vec::new(),
+            vectors: Vec::new(),
+        }
+    }
+
+    /// Return the number of particles in the set.
+    ///
+    pub fn len(&self) -> usize
+    {
+        self.positions.len()
+    }
+
+    /// Return whether the set has no particles.
+    ///
+    pub fn is_empty(&self) -> bool
+    {
+        self.positions.is_empty()
+    }
+
+    /// Add a particle to the set, with zero density, zero radius of support,
+    /// the first phase, zero temperature and zero in every attribute column.
+    ///
+    pub fn push(&mut self, position: ParticlePos<N>, velocity: ParticlePos<N>, mass: f64)
+    {
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.masses.push(mass);
+        self.densities.push(0.0);
+        self.support_radii.push(0.0);
+        self.phases.push(0);
+        self.temperatures.push(0.0);
+
+        for (_, values) in &mut self.scalars
+        {
+            values.push(0.0);
+        }
+        for (_, values) in &mut self.vectors
+        {
+            values.push(ParticlePos::zeros());
+        }
+    }
+
+    /// Add a named scalar attribute column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one value per particle.
+    ///
+    pub fn add_scalar(&mut self, name: impl Into<String>, values: Vec<f64>)
+    {
+        assert_eq!(values.len(), self.len(), "one scalar value per particle");
+        self.scalars.push(( name.into(), values ));
+    }
+
+    /// Add a named vector attribute column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one value per particle.
+    ///
+    pub fn add_vector(&mut self, name: impl Into<String>, values: Vec<ParticlePos<N>>)
+    {
+        assert_eq!(values.len(), self.len(), "one vector value per particle");
+        self.vectors.push(( name.into(), values ));
+    }
+
+    /// Return the named scalar attribute column, if present.
+    ///
+    pub fn scalar(&self, name: &str) -> Option<&[f64]>
+    {
+        self.scalars.iter()
+            .find(|(scalar_name, _)| scalar_name == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Return the named vector attribute column, if present.
+    ///
+    pub fn vector(&self, name: &str) -> Option<&[ParticlePos<N>]>
+    {
+        self.vectors.iter()
+            .find(|(vector_name, _)| vector_name == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// Return the named scalar attribute column for writing, if present.
+    ///
+    pub fn scalar_mut(&mut self, name: &str) -> Option<&mut [f64]>
+    {
+        self.scalars.iter_mut()
+            .find(|(scalar_name, _)| scalar_name == name)
+            .map(|(_, values)| values.as_mut_slice())
+    }
+
+    /// Return the named vector attribute column for writing, if present.
+    ///
+    pub fn vector_mut(&mut self, name: &str) -> Option<&mut [ParticlePos<N>]>
+    {
+        self.vectors.iter_mut()
+            .find(|(vector_name, _)| vector_name == name)
+            .map(|(_, values)| values.as_mut_slice())
+    }
+
+    /// Evaluate the density at every particle with a field kernel, and store
+    /// it in `densities`.
+    ///
+    pub fn update_densities<K: ?Sized + Kernel>(&mut self, kernel: &FieldKernel<N,K>)
+    {
+        let densities = UniformField::new(kernel.clone(), self).densities();
+        self.densities = densities;
+    }
+
+    /// Adapt the radius of support of every particle to its density, so every
+    /// particle has a similar number of neighbours, and store the density.
+    ///
+    /// Solves `ρ(h) = m (η / h)ᴺ` at every particle, with the density
+    /// evaluated with the particle's own radius of support, by Newton-Raphson
+    /// iteration until the largest relative change in any radius is within a
+    /// tolerance. The plain fixed point `h = η (m / ρ)^(1/N)` cycles rather
+    /// than converges for small `η`, so it is only taken, damped, where the
+    /// Newton step is undefined, and every step is limited to halving or
+    /// doubling a radius. Particles without a radius of support start from
+    /// that of the field kernel.
+    ///
+    /// A radius only exists where `η` is large enough that the particle alone,
+    /// `m W(0, h)`, is less dense than `m (η / h)ᴺ`, such as `η > 1.49` for
+    /// [`WendlandC2`] in two dimensions. Below that every radius shrinks and
+    /// the adaptation is unconverged.
+    ///
+    /// The densities are evaluated once more at the final radii.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel`     - The field kernel, whose shape is used at every radius.
+    /// * `eta`        - The ratio `η` of the radius of support to the spacing of
+    ///   particles, around 3 in two dimensions.
+    /// * `tolerance`  - The largest relative change in a radius to stop at.
+    /// * `iterations` - The greatest number of iterations.
+    ///
+    /// [`WendlandC2`]: crate::kernels::WendlandC2
+    ///
+    pub fn adapt_support_radii<K: ?Sized + Kernel>(
+        &mut self,
+        kernel: &FieldKernel<N,K>,
+        eta: f64,
+        tolerance: f64,
+        iterations: usize,
+    ) -> Adaptation
+    {
+        for radius in &mut self.support_radii
+        {
+            if *radius <= 0.0 { *radius = kernel.support_radius() };
+        }
+
+        let mut adaptation = Adaptation::Unconverged { change: f64::INFINITY };
+
+        for iteration in 1..=iterations
+        {
+            let field = AdaptiveField::new(kernel.clone(), self);
+            let densities = field.gather_densities();
+            let derivatives = field.gather_density_derivatives();
+
+            let support_radii = itertools::izip!(&self.support_radii, &self.masses, &densities, &derivatives)
+                .map(|(radius, mass, density, derivative)|
+                {
+                    let target = mass * (eta / radius).powi(N as i32);
+                    let slope = derivative + N as f64 * target / radius;
+
+                    let radius_new = match slope > 0.0
+                    {
+                        true => radius - (density - target) / slope,
+                        false => 0.5 * (radius + eta * (mass / density).powf(1.0 / N as f64)),
+                    };
+
+                    radius_new.clamp(0.5 * radius, 2.0 * radius)
+                })
+                .collect::<Vec<_>>();
+
+            let change = itertools::izip!(&self.support_radii, &support_radii)
+                .map(|(radius, radius_new)| ((radius_new - radius) / radius).abs())
+                .fold(0.0, f64::max);
+
+            self.support_radii = support_radii;
+
+            adaptation = match change <= tolerance
+            {
+                true => Adaptation::Converged { iterations: iteration },
+                false => Adaptation::Unconverged { change },
+            };
+
+            if let Adaptation::Converged { .. } = adaptation { break };
+        }
+
+        self.densities = AdaptiveField::new(kernel.clone(), self).gather_densities();
+
+        adaptation
+    }
+}
+
+/// The outcome of adapting the radii of support of a particle set.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Adaptation
+{
+    /// Every radius changed by no more than the tolerance, after a number of
+    /// iterations.
+    Converged { iterations: usize },
+    /// The greatest number of iterations was taken, with the largest relative
+    /// change in any radius over the last of them.
+    Unconverged { change: f64 },
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::NORMALISATION_TOLERANCE;
+    use crate::kernels::WendlandC2;
+    use util::random::SplitMix64;
+
+    /// A jittered square of particles a unit apart.
+    ///
+    fn particles() -> ParticleSet<2>
+    {
+        let mut random = SplitMix64::new(5);
+        let mut jitter = || random.next_f32() * 0.3 - 0.15;
+
+        let positions = itertools::iproduct!(0..20, 0..20)
+            .map(|(i, j)| ParticlePos::<2>::new(i as f32 + jitter(), j as f32 + jitter()))
+            .collect();
+
+        ParticleSet::from_positions(positions, 1.0)
+    }
+
+    #[test]
+    fn support_radii_converge()
+    {
+        let kernel = FieldKernel::new(WendlandC2, 1.0, NORMALISATION_TOLERANCE);
+
+        for eta in [1.5, 1.6, 2.0, 3.0]
+        {
+            let mut particles = particles();
+            let adaptation = particles.adapt_support_radii(&kernel, eta, 1e-10, 30);
+
+            assert!(matches!(adaptation, Adaptation::Converged { iterations } if iterations <= 15), "{:?} for η = {}", adaptation, eta);
+
+            // The densities are those at the final radii, so every radius
+            // satisfies `h = η (m / ρ)^(1/N)` with them.
+            //
+            for (radius, mass, density) in itertools::izip!(&particles.support_radii, &particles.masses, &particles.densities)
+            {
+                let expected = eta * (mass / density).sqrt();
+                assert!(((radius - expected) / radius).abs() < 1e-8, "h = {} but η (m/ρ)^(1/N) = {} for η = {}", radius, expected, eta);
+            }
+        }
+    }
+
+    #[test]
+    fn support_radii_report_iteration_cap()
+    {
+        let kernel = FieldKernel::new(WendlandC2, 1.0, NORMALISATION_TOLERANCE);
+        let mut particles = particles();
+
+        let adaptation = particles.adapt_support_radii(&kernel, 3.0, 1e-10, 1);
+
+        assert!(matches!(adaptation, Adaptation::Unconverged { change } if change > 1e-10));
+    }
+}
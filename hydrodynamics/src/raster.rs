@@ -0,0 +1,230 @@
+
+use std::io::{self, Write};
+
+use crate::{Kernel, UniformQuantityField};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type RasterPos<const N: usize> = nalgebra::SVector<f32,N>;
+
+/// Represents a quantity sampled onto a regular grid of cells spanning an
+/// axis-aligned box in N-dimensional space.
+///
+/// Values are stored with the first axis varying fastest, so a 2D raster is
+/// stored row by row, each row running along `x`.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+///
+/// ## Fields
+///
+/// * `lower`      - The lower corner of the box.
+/// * `upper`      - The upper corner of the box.
+/// * `resolution` - The number of cells along each axis.
+/// * `values`     - The value sampled at the centre of every cell.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster<const N: usize>
+{
+    pub lower: RasterPos<N>,
+    pub upper: RasterPos<N>,
+    pub resolution: [usize;N],
+    pub values: Vec<f64>,
+}
+
+impl<const N: usize> Raster<N>
+{
+    /// Sample a quantity field at the centre of every cell of a regular grid.
+    ///
+    /// # Arguments
+    ///
+    /// * `field`      - The quantity field to sample.
+    /// * `lower`      - The lower corner of the box spanned by the grid.
+    /// * `upper`      - The upper corner of the box spanned by the grid.
+    /// * `resolution` - The number of cells along each axis.
+    ///
+    pub fn sample<K: ?Sized + Kernel>(
+        field: &UniformQuantityField<'_,N,K>,
+        lower: RasterPos<N>,
+        upper: RasterPos<N>,
+        resolution: [usize;N],
+    ) -> Self
+    {
+        let mut raster = Self {
+            lower,
+            upper,
+            resolution,
+            values: Vec::new(),
+        };
+
+        raster.values = maybe_par_iter!(0..raster.len())
+            .map(|index| field.at(raster.position(raster.cell(index))))
+            .collect();
+
+        raster
+    }
+
+    /// Return the number of cells in the raster.
+    ///
+    pub fn len(&self) -> usize
+    {
+        self.resolution.iter().product()
+    }
+
+    /// Return whether the raster has no cells.
+    ///
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+
+    /// Return the index into `values` of a cell.
+    ///
+    pub fn index(&self, cell: [usize;N]) -> usize
+    {
+        itertools::izip!(cell, self.resolution)
+            .rev()
+            .fold(0, |index, (cell_k, resolution_k)| index * resolution_k + cell_k)
+    }
+
+    /// Return the cell of an index into `values`.
+    ///
+    pub fn cell(&self, mut index: usize) -> [usize;N]
+    {
+        std::array::from_fn(|k|
+        {
+            let cell_k = index % self.resolution[k];
+            index /= self.resolution[k];
+            cell_k
+        })
+    }
+
+    /// Return the position of the centre of a cell.
+    ///
+    pub fn position(&self, cell: [usize;N]) -> RasterPos<N>
+    {
+        RasterPos::from_fn(|k,_|
+        {
+            let width = (self.upper[k] - self.lower[k]) / self.resolution[k] as f32;
+            self.lower[k] + (cell[k] as f32 + 0.5) * width
+        })
+    }
+
+    /// Return the value sampled in a cell.
+    ///
+    pub fn get(&self, cell: [usize;N]) -> f64
+    {
+        self.values[self.index(cell)]
+    }
+
+    /// Return the smallest and largest values in the raster.
+    ///
+    pub fn range(&self) -> (f64, f64)
+    {
+        self.values.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value|
+        {
+            (min.min(*value), max.max(*value))
+        })
+    }
+
+    /// Write the values as a raw array of little-endian `f64`, in the order
+    /// they are stored.
+    ///
+    pub fn write_raw(&self, writer: impl Write) -> io::Result<()>
+    {
+        let mut writer = io::BufWriter::new(writer);
+
+        for value in &self.values
+        {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        writer.flush()
+    }
+}
+
+impl Raster<2>
+{
+    /// Return the raster as a matrix, with a row per `y` and a column per `x`.
+    ///
+    pub fn to_matrix(&self) -> nalgebra::DMatrix<f64>
+    {
+        let [columns, rows] = self.resolution;
+        nalgebra::DMatrix::from_fn(rows, columns, |row, column| self.get([column, row]))
+    }
+
+    /// Return the value of every pixel, scaled from the range of values to
+    /// `[0, 1]`, row by row with the largest `y` at the top.
+    ///
+    fn pixels(&self, range: Option<(f64, f64)>) -> impl Iterator<Item = f64> + '_
+    {
+        let (min, max) = range.unwrap_or(self.range());
+        let [columns, rows] = self.resolution;
+
+        itertools::iproduct!((0..rows).rev(), 0..columns)
+            .map(move |(row, column)|
+            {
+                let value = (self.get([column, row]) - min) / (max - min);
+                if value.is_finite() { value.clamp(0.0, 1.0) } else { 0.0 }
+            })
+    }
+
+    /// Write the raster as a greyscale binary PGM image.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The values mapped to black and white, else the range of the
+    ///   values in the raster.
+    ///
+    pub fn write_pgm(&self, writer: impl Write, range: Option<(f64, f64)>) -> io::Result<()>
+    {
+        let mut writer = io::BufWriter::new(writer);
+        let [columns, rows] = self.resolution;
+
+        write!(writer, "P5\n{} {}\n255\n", columns, rows)?;
+
+        let bytes = self.pixels(range)
+            .map(|value| (value * 255.0).round() as u8)
+            .collect::<Vec<_>>();
+
+        writer.write_all(&bytes)?;
+        writer.flush()
+    }
+
+    /// Write the raster as a colour PNG heatmap, running from blue through
+    /// green to red.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The values mapped to the ends of the colour map, else the
+    ///   range of the values in the raster.
+    ///
+    pub fn write_png(&self, writer: impl Write, range: Option<(f64, f64)>) -> io::Result<()>
+    {
+        let [columns, rows] = self.resolution;
+
+        let mut encoder = png::Encoder::new(writer, columns as u32, rows as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let bytes = self.pixels(range)
+            .flat_map(heatmap)
+            .collect::<Vec<_>>();
+
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&bytes))
+            .map_err(io::Error::other)
+    }
+}
+
+/// Map a value in `[0, 1]` onto a blue, cyan, green, yellow, red colour map.
+///
+fn heatmap(value: f64) -> [u8;3]
+{
+    let channel = |centre: f64| (1.5 - (4.0 * value - centre).abs()).clamp(0.0, 1.0);
+    let to_byte = |channel: f64| (channel * 255.0).round() as u8;
+
+    [to_byte(channel(3.0)), to_byte(channel(2.0)), to_byte(channel(1.0))]
+}
@@ -0,0 +1,129 @@
+
+use crate::{Kernel, UniformField};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// The artificial viscosity of Monaghan (1992), which adds the viscous
+/// pressure
+/// `Π_ij = (-α c̄_ij μ_ij + β μ_ij²) / ρ̄_ij` with
+/// `μ_ij = h v_ij·x_ij / (r² + ε h²)` to the pressure between every pair of
+/// approaching particles, with the mean sound speed `c̄_ij` and density
+/// `ρ̄_ij` of the pair and the radius of support `h`.
+///
+/// The linear term damps the noise of particles jostling at the sound speed,
+/// and the quadratic term stops particles of a violent impact from passing
+/// through each other. The switch of Balsara (1995),
+/// `f_i = |∇·v| / (|∇·v| + |∇×v| + 0.0001 c_i / h)`, scales the viscous
+/// pressure down by `(f_i + f_j) / 2` where the fluid shears rather than
+/// compresses, so vortices are not damped.
+///
+/// ## Fields
+///
+/// * `alpha`   - The coefficient `α` of the term linear in the approach
+///   velocity.
+/// * `beta`    - The coefficient `β` of the term quadratic in the approach
+///   velocity.
+/// * `balsara` - Whether the viscous pressure is scaled by the Balsara switch.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArtificialViscosity
+{
+    pub alpha: f64,
+    pub beta: f64,
+    pub balsara: bool,
+}
+
+impl ArtificialViscosity
+{
+    /// Create a new artificial viscosity, without the Balsara switch.
+    ///
+    pub fn new(alpha: f64, beta: f64) -> Self
+    {
+        Self { alpha, beta, balsara: false }
+    }
+
+    /// Scale the viscous pressure by the Balsara switch.
+    ///
+    pub fn with_balsara_switch(mut self) -> Self
+    {
+        self.balsara = true;
+        self
+    }
+
+    /// Return whether the artificial viscosity adds no viscous pressure.
+    ///
+    pub fn is_none(&self) -> bool
+    {
+        self.alpha == 0.0 && self.beta == 0.0
+    }
+
+    /// Evaluate the Balsara switch at every particle, in the order of the
+    /// particle set, or one everywhere without the switch.
+    ///
+    /// # Arguments
+    ///
+    /// * `field`        - The field of the particles, with up to date densities.
+    /// * `sound_speeds` - The speed of sound at every particle.
+    ///
+    pub fn switches<const N: usize, K: ?Sized + Kernel>(
+        &self,
+        field: &UniformField<'_,N,K>,
+        sound_speeds: &[f64],
+    ) -> Vec<f64>
+    {
+        if !self.balsara || self.is_none()
+        {
+            return vec![1.0; field.particles().len()];
+        }
+
+        let support = field.kernel().support_radius();
+        let velocity_gradients = field.velocity_gradients();
+
+        maybe_par_iter!(0..velocity_gradients.len())
+            .map(|index|
+            {
+                let velocity_gradient = &velocity_gradients[index];
+
+                // The magnitude of the curl is that of the antisymmetric part
+                // of the velocity gradient, in any number of dimensions.
+                //
+                let divergence = velocity_gradient.trace().abs();
+                let curl = ((velocity_gradient - velocity_gradient.transpose()) / 2.0).norm() * std::f64::consts::SQRT_2;
+
+                divergence / (divergence + curl + 0.0001 * sound_speeds[index] / support)
+            })
+            .collect()
+    }
+
+    /// Return the viscous pressure `Π_ij` between a pair of particles, zero
+    /// unless they are approaching each other.
+    ///
+    /// # Arguments
+    ///
+    /// * `support`           - The radius of support of the kernel.
+    /// * `offset`            - The offset `x_j - x_i` from the first particle
+    ///   to the second.
+    /// * `relative_velocity` - The velocity `v_i - v_j` of the first particle
+    ///   relative to the second.
+    /// * `sound_speed`       - The mean speed of sound of the pair.
+    /// * `density`           - The mean density of the pair.
+    ///
+    pub fn viscous_pressure<const N: usize>(
+        &self,
+        support: f64,
+        offset: &FieldVec<N>,
+        relative_velocity: &FieldVec<N>,
+        sound_speed: f64,
+        density: f64,
+    ) -> f64
+    {
+        let separation_rate = -relative_velocity.dot(offset);
+        if separation_rate >= 0.0 { return 0.0 };
+
+        let mu = support * separation_rate / (offset.norm_squared() + 0.01 * support.powi(2));
+        (-self.alpha * sound_speed * mu + self.beta * mu.powi(2)) / density
+    }
+}
@@ -0,0 +1,554 @@
+
+use crate::{FieldKernel, Kernel, NeighbourGrid, ParticleSet};
+use crate::solver::ArtificialViscosity;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldPos<const N: usize> = nalgebra::SVector<f32,N>;
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// Represents a solid the fluid flows around, sampled as a layer of boundary
+/// particles over its surface, after Akinci et al. (2012).
+///
+/// Every boundary particle stands in for the volume `V_b = 1 / Σ_k W_bk` of
+/// solid around it, so an unevenly sampled surface still pushes back on the
+/// fluid evenly. Boundary particles add `ρ₀ V_b W` to the density of the
+/// fluid near them, are pushed on by its pressure, and pull on it through
+/// adhesion. A solid held at a temperature heats or cools the fluid near it
+/// by conduction.
+///
+/// Boundary particles are at rest unless given velocities, as those of a
+/// moving rigid body, which the fluid then feels in its density and
+/// artificial viscosity.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+///
+/// ## Fields
+///
+/// * `positions`   - The position of every boundary particle.
+/// * `volumes`     - The volume of solid every boundary particle stands in for.
+/// * `velocities`  - The velocity of every boundary particle.
+/// * `adhesion`    - The coefficient of adhesion between the fluid and the
+///   solid, zero for a solid the fluid does not wet.
+/// * `temperature` - The temperature the solid is held at, if it is a source
+///   or sink of heat.
+/// * `neighbours`  - The boundary particles bucketed by position.
+///
+#[derive(Clone, Debug)]
+pub struct Collider<const N: usize>
+{
+    positions: Vec<FieldPos<N>>,
+    volumes: Vec<f64>,
+    velocities: Vec<FieldVec<N>>,
+    pub adhesion: f64,
+    pub temperature: Option<f64>,
+    neighbours: NeighbourGrid<N>,
+}
+
+impl<const N: usize> Collider<N>
+{
+    /// Create a new collider from boundary particles sampled over the surface
+    /// of a solid, with their volumes evaluated with a field kernel.
+    ///
+    pub fn new<K: ?Sized + Kernel>(positions: Vec<FieldPos<N>>, kernel: &FieldKernel<N,K>) -> Self
+    {
+        let mut neighbours = NeighbourGrid::new(kernel.support_radius());
+
+        for (index, position) in positions.iter().enumerate()
+        {
+            neighbours.insert(index, position);
+        }
+
+        let mut collider = Self {
+            velocities: vec![FieldVec::zeros(); positions.len()],
+            positions,
+            volumes: Vec::new(),
+            adhesion: 0.0,
+            temperature: None,
+            neighbours,
+        };
+
+        collider.volumes = maybe_par_iter!(0..collider.positions.len())
+            .map(|index|
+            {
+                let kernel_sum = collider.neighbours_within(&collider.positions[index], kernel.support_radius())
+                    .map(|(_other, offset)| kernel.influence(offset.norm()))
+                    .sum::<f64>();
+
+                1.0 / kernel_sum
+            })
+            .collect();
+
+        collider
+    }
+
+    /// Set the coefficient of adhesion between the fluid and the solid.
+    ///
+    pub fn with_adhesion(mut self, adhesion: f64) -> Self
+    {
+        self.adhesion = adhesion;
+        self
+    }
+
+    /// Hold the solid at a temperature, so it heats or cools the fluid.
+    ///
+    pub fn with_temperature(mut self, temperature: f64) -> Self
+    {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Move every boundary particle with a velocity, in the order of the
+    /// boundary particles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not one velocity for every boundary particle.
+    ///
+    pub fn with_velocities(mut self, velocities: Vec<FieldVec<N>>) -> Self
+    {
+        assert_eq!(velocities.len(), self.positions.len(), "one velocity for every boundary particle");
+
+        self.velocities = velocities;
+        self
+    }
+
+    /// Return the position of every boundary particle.
+    ///
+    pub fn positions(&self) -> &[FieldPos<N>]
+    {
+        &self.positions
+    }
+
+    /// Return the volume of solid every boundary particle stands in for.
+    ///
+    pub fn volumes(&self) -> &[f64]
+    {
+        &self.volumes
+    }
+
+    /// Return the velocity of every boundary particle.
+    ///
+    pub fn velocities(&self) -> &[FieldVec<N>]
+    {
+        &self.velocities
+    }
+
+    /// Return the index of, and offset to, every boundary particle within a
+    /// distance of a position.
+    ///
+    pub fn neighbours_within(&self, position: &FieldPos<N>, radius: f64) -> impl Iterator<Item = (usize, FieldVec<N>)> + '_
+    {
+        let position = *position;
+
+        self.neighbours.candidates(&position)
+            .map(move |index| (index, (self.positions[index] - position).map(f64::from)))
+            .filter(move |(_index, offset)| offset.norm() <= radius)
+    }
+
+    /// Evaluate the kernel sum `Σ V_b W_ib` the solid adds to every fluid
+    /// particle, in the order of the particle set, so the kernel sum of a
+    /// particle against the solid is still one.
+    ///
+    pub fn kernel_sums<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+    ) -> Vec<f64>
+    {
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                self.neighbours_within(&particles.positions[index], kernel.support_radius())
+                    .map(|(boundary, offset)| self.volumes[boundary] * kernel.influence(offset.norm()))
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Evaluate the gradient of the kernel sum `Σ V_b ∇W_ib` the solid adds
+    /// to every fluid particle, in the order of the particle set.
+    ///
+    pub fn kernel_sum_gradients<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+    ) -> Vec<FieldVec<N>>
+    {
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                self.neighbours_within(&particles.positions[index], kernel.support_radius())
+                    .map(|(boundary, offset)|
+                    {
+                        let radius = offset.norm();
+                        if radius == 0.0 { return FieldVec::zeros() };
+
+                        -offset * (self.volumes[boundary] * kernel.influence_derivative(radius) / radius)
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Evaluate the divergence of position `-Σ V_b x_ib·∇W_ib` the solid adds
+    /// to every fluid particle, in the order of the particle set.
+    ///
+    pub fn position_divergences<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+    ) -> Vec<f64>
+    {
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                self.neighbours_within(&particles.positions[index], kernel.support_radius())
+                    .map(|(boundary, offset)| -self.volumes[boundary] * offset.norm() * kernel.influence_derivative(offset.norm()))
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Evaluate the density the solid adds to every fluid particle, in the
+    /// order of the particle set.
+    ///
+    /// # Arguments
+    ///
+    /// * `particles`      - The fluid particles.
+    /// * `kernel`         - The field kernel smoothing every particle.
+    /// * `rest_densities` - The density at rest of the fluid of every particle.
+    ///
+    pub fn densities<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+        rest_densities: &[f64],
+    ) -> Vec<f64>
+    {
+        itertools::izip!(self.kernel_sums(particles, kernel), rest_densities)
+            .map(|(kernel_sum, rest_density)| rest_density * kernel_sum)
+            .collect()
+    }
+
+    /// Evaluate the rate of change of the density the solid adds to every
+    /// fluid particle as it moves, `dρ_i/dt = Σ ρ₀ V_b v_ib·∇W_ib`, in the
+    /// order of the particle set.
+    ///
+    /// # Arguments
+    ///
+    /// * `particles`      - The fluid particles.
+    /// * `kernel`         - The field kernel smoothing every particle.
+    /// * `rest_densities` - The density at rest of the fluid of every particle.
+    ///
+    pub fn density_rates<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+        rest_densities: &[f64],
+    ) -> Vec<f64>
+    {
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                let velocity = particles.velocities[index].map(f64::from);
+
+                self.neighbours_within(&particles.positions[index], kernel.support_radius())
+                    .map(|(boundary, offset)|
+                    {
+                        let radius = offset.norm();
+                        if radius == 0.0 { return 0.0 };
+
+                        let kernel_gradient = -offset * (kernel.influence_derivative(radius) / radius);
+                        let relative_velocity = velocity - self.velocities[boundary];
+                        rest_densities[index] * self.volumes[boundary] * relative_velocity.dot(&kernel_gradient)
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Evaluate the acceleration of every fluid particle due to the solid, in
+    /// the order of the particle set.
+    ///
+    /// The pressure of every fluid particle pushes it away from the boundary
+    /// with `a_i = -Σ ρ₀ V_b p_i/ρ_i² ∇W_ib`, and adhesion pulls it towards the
+    /// boundary with the adhesion spline of Akinci et al. (2013). Negative
+    /// pressures are clamped to zero, so the thinner fluid near a boundary is
+    /// not drawn through it.
+    ///
+    /// The viscous pressure of an artificial viscosity also slows particles
+    /// approaching the boundary, as it moves, without the Balsara switch
+    /// since the solid does not shear.
+    ///
+    /// # Arguments
+    ///
+    /// * `particles`            - The fluid particles, with up to date
+    ///   densities.
+    /// * `kernel`               - The field kernel smoothing every particle.
+    /// * `pressures`            - The pressure at every fluid particle.
+    /// * `rest_densities`       - The density at rest of the fluid of every
+    ///   particle.
+    /// * `sound_speeds`         - The speed of sound at every fluid particle.
+    /// * `artificial_viscosity` - The artificial viscosity between the fluid
+    ///   and the solid.
+    ///
+    pub fn accelerations<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+        pressures: &[f64],
+        rest_densities: &[f64],
+        sound_speeds: &[f64],
+        artificial_viscosity: &ArtificialViscosity,
+    ) -> Vec<FieldVec<N>>
+    {
+        self.pair_accelerations(particles, kernel, pressures, rest_densities, sound_speeds, artificial_viscosity)
+            .into_iter()
+            .map(|pairs| pairs.into_iter().map(|(_boundary, acceleration)| acceleration).sum())
+            .collect()
+    }
+
+    /// Evaluate the force the fluid exerts on every boundary particle, in the
+    /// order of the boundary particles.
+    ///
+    /// Every force is the reaction `F_b = -Σ m_i a_ib` to the accelerations
+    /// the solid gives the fluid particles near it, so the fluid pushes a
+    /// rigid body built of the boundary particles as hard as the body pushes
+    /// back on the fluid.
+    ///
+    /// # Arguments
+    ///
+    /// * `particles`            - The fluid particles, with up to date
+    ///   densities.
+    /// * `kernel`               - The field kernel smoothing every particle.
+    /// * `pressures`            - The pressure at every fluid particle.
+    /// * `rest_densities`       - The density at rest of the fluid of every
+    ///   particle.
+    /// * `sound_speeds`         - The speed of sound at every fluid particle.
+    /// * `artificial_viscosity` - The artificial viscosity between the fluid
+    ///   and the solid.
+    ///
+    pub fn forces<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+        pressures: &[f64],
+        rest_densities: &[f64],
+        sound_speeds: &[f64],
+        artificial_viscosity: &ArtificialViscosity,
+    ) -> Vec<FieldVec<N>>
+    {
+        let pair_accelerations = self.pair_accelerations(particles, kernel, pressures, rest_densities, sound_speeds, artificial_viscosity);
+
+        let mut forces = vec![FieldVec::zeros(); self.positions.len()];
+
+        // Visit the fluid particles in order, so the forces are summed in the
+        // same order every time.
+        //
+        for (mass, pairs) in itertools::izip!(&particles.masses, pair_accelerations)
+        {
+            for (boundary, acceleration) in pairs
+            {
+                forces[boundary] -= acceleration * *mass;
+            }
+        }
+
+        forces
+    }
+
+    /// Evaluate the acceleration of every fluid particle due to every
+    /// boundary particle near it, from pressure, artificial viscosity and
+    /// adhesion, as the index of the boundary particle and the acceleration,
+    /// in the order of the particle set.
+    ///
+    fn pair_accelerations<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+        pressures: &[f64],
+        rest_densities: &[f64],
+        sound_speeds: &[f64],
+        artificial_viscosity: &ArtificialViscosity,
+    ) -> Vec<Vec<(usize, FieldVec<N>)>>
+    {
+        let support = kernel.support_radius();
+
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                let pressure_term = pressures[index].max(0.0) / particles.densities[index].powi(2);
+                let velocity = particles.velocities[index].map(f64::from);
+
+                self.neighbours_within(&particles.positions[index], support)
+                    .map(|(boundary, offset)|
+                    {
+                        let radius = offset.norm();
+                        if radius == 0.0 { return (boundary, FieldVec::zeros()) };
+
+                        let boundary_mass = rest_densities[index] * self.volumes[boundary];
+                        let kernel_gradient = -offset * (kernel.influence_derivative(radius) / radius);
+
+                        let viscous_pressure = artificial_viscosity.viscous_pressure(
+                            support,
+                            &offset,
+                            &(velocity - self.velocities[boundary]),
+                            sound_speeds[index],
+                            particles.densities[index]);
+
+                        let pressure = kernel_gradient * (-boundary_mass * (pressure_term + viscous_pressure));
+                        let adhesion = offset * (self.adhesion * boundary_mass * adhesion(support, radius) / radius);
+
+                        (boundary, pressure + adhesion)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Evaluate the rate of change of temperature of every fluid particle due
+    /// to conduction of heat from the solid, in the order of the particle set.
+    ///
+    /// Uses the same form as the Laplacian of a quantity field, with every
+    /// boundary particle at the temperature of the solid, and is zero if the
+    /// solid is not held at a temperature.
+    ///
+    /// # Arguments
+    ///
+    /// * `particles`     - The fluid particles, with up to date densities.
+    /// * `kernel`        - The field kernel smoothing every particle.
+    /// * `diffusivities` - The thermal diffusivity at every fluid particle.
+    ///
+    pub fn heating_rates<K: ?Sized + Kernel>(
+        &self,
+        particles: &ParticleSet<N>,
+        kernel: &FieldKernel<N,K>,
+        diffusivities: &[f64],
+    ) -> Vec<f64>
+    {
+        let Some(temperature) = self.temperature else { return vec![0.0; particles.len()] };
+
+        let epsilon = 0.01 * kernel.support_radius().powi(2);
+
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                let laplacian = self.neighbours_within(&particles.positions[index], kernel.support_radius())
+                    .map(|(boundary, offset)|
+                    {
+                        let radius = offset.norm();
+                        let derivative = kernel.influence_derivative(radius);
+
+                        2.0 * self.volumes[boundary] * (particles.temperatures[index] - temperature)
+                            * radius * derivative / (radius.powi(2) + epsilon)
+                    })
+                    .sum::<f64>();
+
+                diffusivities[index] * laplacian
+            })
+            .collect()
+    }
+}
+
+impl Collider<2>
+{
+    /// Create a new collider of a circle, with boundary particles spaced
+    /// evenly around its circumference.
+    ///
+    /// # Arguments
+    ///
+    /// * `centre`  - The centre of the circle.
+    /// * `radius`  - The radius of the circle.
+    /// * `spacing` - The greatest spacing of the boundary particles.
+    /// * `kernel`  - The field kernel smoothing every particle.
+    ///
+    pub fn circle<K: ?Sized + Kernel>(
+        centre: FieldPos<2>,
+        radius: f32,
+        spacing: f32,
+        kernel: &FieldKernel<2,K>,
+    ) -> Self
+    {
+        let count = (std::f32::consts::TAU * radius / spacing).ceil().max(3.0) as usize;
+
+        let positions = (0..count)
+            .map(|index|
+            {
+                let angle = std::f32::consts::TAU * index as f32 / count as f32;
+                centre + nalgebra::Vector2::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+
+        Self::new(positions, kernel)
+    }
+
+    /// Create a new collider of a polygon, with boundary particles spaced
+    /// evenly along every edge.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - The vertices of the polygon, in order around it.
+    /// * `spacing`  - The greatest spacing of the boundary particles.
+    /// * `kernel`   - The field kernel smoothing every particle.
+    ///
+    pub fn polygon<K: ?Sized + Kernel>(
+        vertices: &[FieldPos<2>],
+        spacing: f32,
+        kernel: &FieldKernel<2,K>,
+    ) -> Self
+    {
+        let positions = vertices.iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .flat_map(|(start, end)| sample_edge(*start, *end, spacing))
+            .collect();
+
+        Self::new(positions, kernel)
+    }
+
+    /// Create a new collider of an open chain of edges, such as a wall, with
+    /// boundary particles spaced evenly along every edge and at both ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - The vertices of the chain, in order along it.
+    /// * `spacing`  - The greatest spacing of the boundary particles.
+    /// * `kernel`   - The field kernel smoothing every particle.
+    ///
+    pub fn polyline<K: ?Sized + Kernel>(
+        vertices: &[FieldPos<2>],
+        spacing: f32,
+        kernel: &FieldKernel<2,K>,
+    ) -> Self
+    {
+        let positions = vertices.windows(2)
+            .flat_map(|edge| sample_edge(edge[0], edge[1], spacing))
+            .chain(vertices.last().copied())
+            .collect();
+
+        Self::new(positions, kernel)
+    }
+}
+
+/// Return points spaced evenly along an edge, from its start up to but not
+/// including its end.
+///
+pub(super) fn sample_edge(start: FieldPos<2>, end: FieldPos<2>, spacing: f32) -> impl Iterator<Item = FieldPos<2>>
+{
+    let count = ((end - start).norm() / spacing).ceil().max(1.0) as usize;
+    (0..count).map(move |index| start + (end - start) * (index as f32 / count as f32))
+}
+
+/// The adhesion spline of Akinci et al., attractive between half the radius
+/// of support `h` and the radius of support, and zero elsewhere.
+///
+fn adhesion(h: f64, r: f64) -> f64
+{
+    match r
+    {
+        r if r > h || 2.0 * r <= h => 0.0,
+        r => 0.007 / h.powf(3.25) * (-4.0 * r.powi(2) / h + 6.0 * r - 2.0 * h).powf(0.25),
+    }
+}
@@ -0,0 +1,151 @@
+
+use crate::{GradientCorrection, Kernel, UniformField};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// How the density at every particle is found as the particles move.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DensityEvolution
+{
+    /// The density is summed afresh from the positions of neighbouring
+    /// particles every step, `ρ_i = m_i Σ W_ij`.
+    #[default] Summation,
+    /// The density is integrated through time by the continuity equation
+    /// `dρ_i/dt = m_i Σ v_ij·∇W_ij`, with an optional density diffusion, so a
+    /// particle at a free surface keeps the density of the fluid.
+    Continuity,
+}
+
+/// The δ-SPH diffusion of density, which adds `δ h c₀ Σ V_j ψ_ij·∇W_ij` to
+/// the rate of change of density of every particle, with the radius of
+/// support `h` and the speed of sound at rest `c₀`, to smooth out the high
+/// frequency noise of density evolved by the continuity equation.
+///
+/// Density only diffuses between particles of the same phase, so it does not
+/// smear out across the interface between two fluids.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DensityDiffusion
+{
+    /// No diffusion of density.
+    #[default] None,
+    /// The diffusion of Molteni and Colagrossi (2009),
+    /// `ψ_ij = 2 (ρ_j - ρ_i) x_ji / r²`, which also diffuses the hydrostatic
+    /// gradient of density, so a column of fluid slowly loses its
+    /// stratification.
+    MolteniColagrossi { delta: f64 },
+    /// The diffusion of Antuono et al. (2010),
+    /// `ψ_ij = (2 (ρ_j - ρ_i) - (∇ρ_i + ∇ρ_j)·x_ji) x_ji / r²`, which removes
+    /// the linear part of the density with its renormalised gradient, so the
+    /// hydrostatic gradient of density is kept. Near a solid, where the
+    /// density of the fluid is cut off and far from linear, it falls back to
+    /// the diffusion of Molteni and Colagrossi.
+    Antuono { delta: f64 },
+}
+
+impl DensityDiffusion
+{
+    /// Evaluate the rate of change of density of every particle due to
+    /// diffusion, in the order of the particle set.
+    ///
+    /// # Arguments
+    ///
+    /// * `field`        - The field of the particles, with up to date densities.
+    /// * `densities`    - The density to diffuse at every particle.
+    /// * `sound_speeds` - The speed of sound at rest at every particle.
+    /// * `near_solid`   - Whether every particle is near a solid.
+    ///
+    pub fn rates<const N: usize, K: ?Sized + Kernel>(
+        &self,
+        field: &UniformField<'_,N,K>,
+        densities: &[f64],
+        sound_speeds: &[f64],
+        near_solid: &[bool],
+    ) -> Vec<f64>
+    {
+        let particles = field.particles();
+
+        let (delta, gradients) = match *self
+        {
+            DensityDiffusion::None => return vec![0.0; particles.len()],
+            DensityDiffusion::MolteniColagrossi { delta } => (delta, None),
+            DensityDiffusion::Antuono { delta } =>
+            {
+                let gradients = field.sample(densities)
+                    .kernel_gradient(GradientCorrection::Renormalised)
+                    .gradients()
+                    .iter()
+                    .map(|gradient| FieldVec::from(*gradient))
+                    .collect::<Vec<_>>();
+
+                (delta, Some(gradients))
+            },
+        };
+
+        let support = field.kernel().support_radius();
+        let epsilon = 0.01 * support.powi(2);
+
+        maybe_par_iter!(0..particles.len())
+            .map(|index|
+            {
+                let diffusion = field.neighbours_within(&particles.positions[index])
+                    .filter(|(other, _offset)| particles.phases[*other] == particles.phases[index])
+                    .map(|(other, offset)|
+                    {
+                        let difference = 2.0 * (densities[other] - densities[index]);
+
+                        // The density difference of the linear part of the
+                        // density, which the Antuono diffusion leaves alone
+                        // away from solids.
+                        //
+                        let linear = gradients.as_ref()
+                            .filter(|_| !near_solid[index] && !near_solid[other])
+                            .map(|gradients| (gradients[index] + gradients[other]).dot(&offset))
+                            .unwrap_or(0.0);
+
+                        let volume = particles.masses[other] / particles.densities[other];
+                        let psi = offset * ((difference - linear) / (offset.norm_squared() + epsilon));
+
+                        volume * psi.dot(&field.influence_gradient(&offset))
+                    })
+                    .sum::<f64>();
+
+                delta * support * sound_speeds[index] * diffusion
+            })
+            .collect()
+    }
+}
+
+/// Evaluate the rate of change of density of every particle by the
+/// continuity equation, `dρ_i/dt = m_i Σ v_ij·∇W_ij`, in the order of the
+/// particle set.
+///
+/// This is the rate of change of the number density `ρ_i = m_i Σ W_ij`, so
+/// density evolves as it would by summation, and stays sharp across the
+/// interface between fluids of different densities.
+///
+pub fn continuity_rates<const N: usize, K: ?Sized + Kernel>(field: &UniformField<'_,N,K>) -> Vec<f64>
+{
+    let particles = field.particles();
+
+    maybe_par_iter!(0..particles.len())
+        .map(|index|
+        {
+            let velocity = particles.velocities[index].map(f64::from);
+
+            let divergence = field.neighbours_within(&particles.positions[index])
+                .map(|(other, offset)|
+                {
+                    let relative_velocity = velocity - particles.velocities[other].map(f64::from);
+                    relative_velocity.dot(&field.influence_gradient(&offset))
+                })
+                .sum::<f64>();
+
+            particles.masses[index] * divergence
+        })
+        .collect()
+}
@@ -0,0 +1,52 @@
+
+/// The equation of state relating the density of a fluid to its pressure.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EquationOfState
+{
+    /// A pressure proportional to the excess density, `p = k (ρ - ρ₀)`.
+    Linear { rest_density: f64, stiffness: f64 },
+    /// The Tait equation of a weakly compressible fluid,
+    /// `p = k ((ρ / ρ₀)^γ - 1)`, with `γ` usually 7 for water.
+    Tait { rest_density: f64, stiffness: f64, exponent: f64 },
+}
+
+impl EquationOfState
+{
+    /// Return the density at which the fluid is at zero pressure.
+    ///
+    pub fn rest_density(&self) -> f64
+    {
+        match *self
+        {
+            EquationOfState::Linear { rest_density, .. } => rest_density,
+            EquationOfState::Tait { rest_density, .. } => rest_density,
+        }
+    }
+
+    /// Return the pressure of the fluid at a density.
+    ///
+    pub fn pressure(&self, density: f64) -> f64
+    {
+        match *self
+        {
+            EquationOfState::Linear { rest_density, stiffness } =>
+                stiffness * (density - rest_density),
+            EquationOfState::Tait { rest_density, stiffness, exponent } =>
+                stiffness * ((density / rest_density).powf(exponent) - 1.0),
+        }
+    }
+
+    /// Return the speed of sound in the fluid at a density, `c = √(dp/dρ)`.
+    ///
+    pub fn sound_speed(&self, density: f64) -> f64
+    {
+        match *self
+        {
+            EquationOfState::Linear { stiffness, .. } =>
+                stiffness.sqrt(),
+            EquationOfState::Tait { rest_density, stiffness, exponent } =>
+                (stiffness * exponent / rest_density * (density / rest_density).powf(exponent - 1.0)).sqrt(),
+        }
+    }
+}
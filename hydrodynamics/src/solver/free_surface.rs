@@ -0,0 +1,89 @@
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// The detection of particles on the free surface of the fluid, by two
+/// criteria which both fall where the support of a particle is cut off by the
+/// surface.
+///
+/// The deficiency of the kernel sum `C_i = Σ V_j W_ij`, which is one where the
+/// support of a particle is filled with fluid or solid and falls to around a
+/// half at a flat free surface. The divergence of position
+/// `∇·r_i = -Σ V_j x_ij·∇W_ij` of Lee et al. (2008), which is the number of
+/// dimensions inside the fluid and falls to around half of it at a flat free
+/// surface, and which still finds particles that the kernel sum misses where
+/// neighbours bunch up close to the surface.
+///
+/// A particle is on the free surface if it meets either criterion.
+///
+/// ## Fields
+///
+/// * `kernel_sum_threshold` - The kernel sum below which a particle is on the
+///   free surface.
+/// * `divergence_threshold` - The divergence of position below which a
+///   particle is on the free surface, as a fraction of the number of
+///   dimensions.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FreeSurfaceDetection
+{
+    pub kernel_sum_threshold: f64,
+    pub divergence_threshold: f64,
+}
+
+impl Default for FreeSurfaceDetection
+{
+    fn default() -> Self
+    {
+        Self { kernel_sum_threshold: 0.85, divergence_threshold: 0.75 }
+    }
+}
+
+impl FreeSurfaceDetection
+{
+    /// Return whether a particle is on the free surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_sum`          - The kernel sum at the particle.
+    /// * `position_divergence` - The divergence of position at the particle.
+    ///
+    pub fn is_free_surface<const N: usize>(&self, kernel_sum: f64, position_divergence: f64) -> bool
+    {
+        kernel_sum < self.kernel_sum_threshold
+            || position_divergence < self.divergence_threshold * N as f64
+    }
+
+    /// Return the outward unit normal of every particle on the free surface,
+    /// down the gradient of the kernel sum, and `None` for every particle
+    /// inside the fluid, in the order of the particle set.
+    ///
+    /// A particle on the free surface with no gradient of the kernel sum, such
+    /// as a lone particle, has a zero normal.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel_sums`          - The kernel sum at every particle.
+    /// * `position_divergences` - The divergence of position at every
+    ///   particle.
+    /// * `kernel_sum_gradients` - The gradient of the kernel sum at every
+    ///   particle.
+    ///
+    pub fn normals<const N: usize>(
+        &self,
+        kernel_sums: &[f64],
+        position_divergences: &[f64],
+        kernel_sum_gradients: &[FieldVec<N>],
+    ) -> Vec<Option<FieldVec<N>>>
+    {
+        itertools::izip!(kernel_sums, position_divergences, kernel_sum_gradients)
+            .map(|(kernel_sum, position_divergence, gradient)|
+            {
+                match self.is_free_surface::<N>(*kernel_sum, *position_divergence)
+                {
+                    true => Some((-gradient).try_normalize(0.0).unwrap_or_else(FieldVec::zeros)),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,625 @@
+
+use crate::{FieldKernel, GradientCorrection, ParticleSet, UniformField};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+mod artificial_viscosity;
+pub use artificial_viscosity::*;
+
+mod collider;
+pub use collider::*;
+
+mod density;
+pub use density::*;
+
+mod equation_of_state;
+pub use equation_of_state::*;
+
+mod free_surface;
+pub use free_surface::*;
+
+mod phase;
+pub use phase::*;
+
+mod pressure;
+pub use pressure::*;
+
+mod rheology;
+pub use rheology::*;
+
+mod rigid_body;
+pub use rigid_body::*;
+
+mod shifting;
+pub use shifting::*;
+
+mod surface_tension;
+pub use surface_tension::*;
+
+mod viscosity;
+pub use viscosity::*;
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// Steps a set of particles through time by smoothed particle hydrodynamics,
+/// summing the acceleration of every particle from each of the forces on it.
+///
+/// Every particle belongs to one of the phases of the solver, by the index in
+/// the phases of the particle set. Densities are evaluated from the number
+/// density, `ρ_i = m_i Σ W_ij`, so the density of each fluid stays sharp
+/// across the interface between two fluids, where the masses of particles
+/// jump. They are either summed afresh every step or integrated through time
+/// by the continuity equation, with an optional δ-SPH diffusion.
+///
+/// Particles also carry a temperature, which diffuses through the fluid by
+/// the Laplacian of the temperature field, is exchanged with colliders held
+/// at a temperature, and drives buoyancy and the viscosity of every phase.
+///
+/// ## Type Parameters
+///
+/// * `N` - The number of dimensions in the space.
+///
+/// ## Fields
+///
+/// * `kernel`               - The field kernel smoothing every particle.
+/// * `phases`               - The material of every fluid in the simulation.
+/// * `surface_tension`      - The model of surface tension between the fluid
+///   and empty space.
+/// * `artificial_viscosity` - The artificial viscosity between approaching
+///   particles, and between particles and colliders.
+/// * `density_evolution`    - How the density at every particle is found.
+/// * `density_diffusion`    - The diffusion of density evolved by the
+///   continuity equation.
+/// * `shifting`             - The shifting of particles after every step.
+/// * `free_surface`         - The detection of particles on the free
+///   surface.
+/// * `colliders`            - The solids the fluid flows around.
+/// * `gravity`              - The acceleration due to gravity, which drives
+///   buoyancy.
+///
+#[derive(Clone)]
+pub struct Solver<const N: usize>
+{
+    pub kernel: FieldKernel<N>,
+    pub phases: Vec<Phase>,
+    pub surface_tension: SurfaceTension,
+    pub artificial_viscosity: ArtificialViscosity,
+    pub density_evolution: DensityEvolution,
+    pub density_diffusion: DensityDiffusion,
+    pub shifting: ParticleShifting,
+    pub free_surface: FreeSurfaceDetection,
+    pub colliders: Vec<Collider<N>>,
+    pub gravity: FieldVec<N>,
+}
+
+impl<const N: usize> Solver<N>
+{
+    /// Create a new solver for fluids of one or more phases, with summation
+    /// density and without surface tension, artificial viscosity, shifting,
+    /// colliders or gravity.
+    ///
+    pub fn new(kernel: FieldKernel<N>, phases: Vec<Phase>) -> Self
+    {
+        Self {
+            kernel,
+            phases,
+            surface_tension: SurfaceTension::None,
+            artificial_viscosity: ArtificialViscosity::default(),
+            density_evolution: DensityEvolution::Summation,
+            density_diffusion: DensityDiffusion::None,
+            shifting: ParticleShifting::default(),
+            free_surface: FreeSurfaceDetection::default(),
+            colliders: Vec::new(),
+            gravity: FieldVec::zeros(),
+        }
+    }
+
+    /// Evaluate the density at every particle by summation, from both the
+    /// fluid and the colliders near it, and store it in the densities of the
+    /// particle set.
+    ///
+    /// Under continuity density only particles without a density yet, such
+    /// as those just added, are summed, and the others keep the density they
+    /// have evolved to.
+    ///
+    pub fn update_densities(&self, particles: &mut ParticleSet<N>)
+    {
+        let continuity = matches!(self.density_evolution, DensityEvolution::Continuity);
+        if continuity && particles.densities.iter().all(|density| *density > 0.0) { return };
+
+        let number_densities = UniformField::new(self.kernel.clone(), particles).number_densities();
+
+        let mut densities = itertools::izip!(&particles.masses, number_densities)
+            .map(|(mass, number_density)| mass * number_density)
+            .collect::<Vec<_>>();
+
+        let rest_densities = self.rest_densities(particles);
+
+        for collider in &self.colliders
+        {
+            let collider_densities = collider.densities(particles, &self.kernel, &rest_densities);
+
+            for (density, collider_density) in itertools::izip!(&mut densities, collider_densities)
+            {
+                *density += collider_density;
+            }
+        }
+
+        for (density, summed) in itertools::izip!(&mut particles.densities, densities)
+        {
+            if !continuity || *density <= 0.0
+            {
+                *density = summed;
+            }
+        }
+    }
+
+    /// Evaluate the rate of change of density of every particle by the
+    /// continuity equation, from both the fluid and the colliders near it,
+    /// and its diffusion, in the order of the particle set.
+    ///
+    /// Rates are evaluated from the densities of the particle set, so those
+    /// must be up to date.
+    ///
+    pub fn density_rates(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        let field = UniformField::new(self.kernel.clone(), particles);
+
+        let rest_sound_speeds = self.phases_of(particles)
+            .map(|phase| phase.equation_of_state.sound_speed(phase.rest_density()))
+            .collect::<Vec<_>>();
+
+        let rest_densities = self.rest_densities(particles);
+
+        // Only the density of the fluid itself diffuses, without that the
+        // colliders add, so the denser fluid against a solid does not bleed
+        // away and let particles through it.
+        //
+        let mut fluid_densities = particles.densities.clone();
+        let mut near_solid = vec![false; particles.len()];
+
+        for collider in &self.colliders
+        {
+            let collider_densities = collider.densities(particles, &self.kernel, &rest_densities);
+
+            for (density, near, collider_density) in itertools::izip!(&mut fluid_densities, &mut near_solid, collider_densities)
+            {
+                *density -= collider_density;
+                *near |= collider_density > 0.0;
+            }
+        }
+
+        let diffusion_rates = self.density_diffusion.rates(&field, &fluid_densities, &rest_sound_speeds, &near_solid);
+
+        let mut rates = continuity_rates(&field);
+
+        for (rate, diffusion_rate) in itertools::izip!(&mut rates, diffusion_rates)
+        {
+            *rate += diffusion_rate;
+        }
+
+        for collider in &self.colliders
+        {
+            let collider_rates = collider.density_rates(particles, &self.kernel, &rest_densities);
+
+            for (rate, collider_rate) in itertools::izip!(&mut rates, collider_rates)
+            {
+                *rate += collider_rate;
+            }
+        }
+
+        rates
+    }
+
+    /// Return the phase of every particle, in the order of the particle set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a particle is in a phase the solver does not have.
+    ///
+    pub fn phases_of<'a>(&'a self, particles: &'a ParticleSet<N>) -> impl Iterator<Item = &'a Phase> + 'a
+    {
+        particles.phases.iter().map(|phase| &self.phases[*phase])
+    }
+
+    /// Return the density at rest of the fluid of every particle, in the order
+    /// of the particle set.
+    ///
+    pub fn rest_densities(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        self.phases_of(particles)
+            .map(Phase::rest_density)
+            .collect()
+    }
+
+    /// Return the volume at rest `V = m / ρ₀` of every particle, in the order
+    /// of the particle set.
+    ///
+    pub fn rest_volumes(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        itertools::izip!(&particles.masses, self.phases_of(particles))
+            .map(|(mass, phase)| mass / phase.rest_density())
+            .collect()
+    }
+
+    /// Evaluate the pressure at every particle from its density, with the
+    /// equation of state of its phase, in the order of the particle set.
+    ///
+    pub fn pressures(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        itertools::izip!(self.phases_of(particles), &particles.densities)
+            .map(|(phase, density)| phase.equation_of_state.pressure(*density))
+            .collect()
+    }
+
+    /// Evaluate the kernel sum `C_i = Σ V_j W_ij` at every particle, from both
+    /// the fluid and the colliders near it, in the order of the particle set.
+    ///
+    /// Every fluid particle takes its volume at rest, `V = m / ρ₀`, rather
+    /// than from its density, which falls with the kernel sum towards a free
+    /// surface and would hide the deficiency.
+    ///
+    pub fn kernel_sums(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        let field = UniformField::new(self.kernel.clone(), particles);
+        let rest_volumes = self.rest_volumes(particles);
+
+        let mut kernel_sums = maybe_par_iter!(&particles.positions)
+            .map(|position|
+            {
+                field.neighbours_within(position)
+                    .map(|(other, offset)| rest_volumes[other] * self.kernel.influence(offset.norm()))
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+
+        for collider in &self.colliders
+        {
+            for (kernel_sum, collider_sum) in itertools::izip!(&mut kernel_sums, collider.kernel_sums(particles, &self.kernel))
+            {
+                *kernel_sum += collider_sum;
+            }
+        }
+
+        kernel_sums
+    }
+
+    /// Evaluate the gradient of the kernel sum `∇C_i = Σ V_j ∇W_ij` at every
+    /// particle, from both the fluid and the colliders near it, with the
+    /// volume at rest of every fluid particle, in the order of the particle
+    /// set.
+    ///
+    pub fn kernel_sum_gradients(&self, particles: &ParticleSet<N>) -> Vec<FieldVec<N>>
+    {
+        let field = UniformField::new(self.kernel.clone(), particles);
+        let rest_volumes = self.rest_volumes(particles);
+
+        let mut gradients = maybe_par_iter!(&particles.positions)
+            .map(|position|
+            {
+                field.neighbours_within(position)
+                    .map(|(other, offset)| field.influence_gradient(&offset) * rest_volumes[other])
+                    .sum::<FieldVec<N>>()
+            })
+            .collect::<Vec<_>>();
+
+        for collider in &self.colliders
+        {
+            accumulate(&mut gradients, collider.kernel_sum_gradients(particles, &self.kernel));
+        }
+
+        gradients
+    }
+
+    /// Evaluate the divergence of position `∇·r_i = -Σ V_j x_ij·∇W_ij` at
+    /// every particle, from both the fluid and the colliders near it, with the
+    /// volume at rest of every fluid particle, in the order of the particle
+    /// set.
+    ///
+    pub fn position_divergences(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        let field = UniformField::new(self.kernel.clone(), particles);
+        let rest_volumes = self.rest_volumes(particles);
+
+        let mut divergences = maybe_par_iter!(&particles.positions)
+            .map(|position|
+            {
+                field.neighbours_within(position)
+                    .map(|(other, offset)| rest_volumes[other] * offset.dot(&field.influence_gradient(&offset)))
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+
+        for collider in &self.colliders
+        {
+            for (divergence, collider_divergence) in itertools::izip!(&mut divergences, collider.position_divergences(particles, &self.kernel))
+            {
+                *divergence += collider_divergence;
+            }
+        }
+
+        divergences
+    }
+
+    /// Return the outward unit normal of every particle on the free surface
+    /// of the fluid, and `None` for every particle inside it, in the order of
+    /// the particle set.
+    ///
+    /// A particle against a collider is not on the free surface, since the
+    /// solid fills the support of the particle as the fluid would.
+    ///
+    pub fn free_surface_normals(&self, particles: &ParticleSet<N>) -> Vec<Option<FieldVec<N>>>
+    {
+        self.free_surface.normals(
+            &self.kernel_sums(particles),
+            &self.position_divergences(particles),
+            &self.kernel_sum_gradients(particles))
+    }
+
+    /// Return whether every particle is on the free surface of the fluid, in
+    /// the order of the particle set.
+    ///
+    pub fn free_surface(&self, particles: &ParticleSet<N>) -> Vec<bool>
+    {
+        itertools::izip!(self.kernel_sums(particles), self.position_divergences(particles))
+            .map(|(kernel_sum, position_divergence)| self.free_surface.is_free_surface::<N>(kernel_sum, position_divergence))
+            .collect()
+    }
+
+    /// Shift every particle by the particle shifting of the solver, from the
+    /// velocities of the particle set.
+    ///
+    /// Under continuity density the density of every shifted particle is
+    /// corrected by its gradient, `ρ_i += ∇ρ_i·δr_i`, to the density of the
+    /// fluid where it is shifted to.
+    ///
+    pub fn shift_particles(&self, particles: &mut ParticleSet<N>, timestep: f64)
+    {
+        if self.shifting.is_none() { return };
+
+        let speeds = particles.velocities.iter()
+            .map(|velocity| velocity.norm() as f64)
+            .collect::<Vec<_>>();
+
+        let shifts = self.shifting.shifts(
+            self.kernel.support_radius(),
+            &speeds,
+            &self.kernel_sum_gradients(particles),
+            &self.free_surface_normals(particles),
+            timestep);
+
+        if let DensityEvolution::Continuity = self.density_evolution
+        {
+            let field = UniformField::new(self.kernel.clone(), particles);
+            let gradients = field.sample(&particles.densities[..])
+                .kernel_gradient(GradientCorrection::None)
+                .gradients()
+                .iter()
+                .map(|gradient| FieldVec::from(*gradient))
+                .collect::<Vec<_>>();
+
+            for (density, gradient, shift) in itertools::izip!(&mut particles.densities, gradients, &shifts)
+            {
+                *density += gradient.dot(shift);
+            }
+        }
+
+        for (position, shift) in itertools::izip!(&mut particles.positions, shifts)
+        {
+            *position += shift.map(|v| v as f32);
+        }
+    }
+
+    /// Evaluate the speed of sound at every particle from its density, with
+    /// the equation of state of its phase, in the order of the particle set.
+    ///
+    pub fn sound_speeds(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        itertools::izip!(self.phases_of(particles), &particles.densities)
+            .map(|(phase, density)| phase.equation_of_state.sound_speed(*density))
+            .collect()
+    }
+
+    /// Return the dynamic viscosity at every particle, at its temperature and
+    /// the shear rate of the fluid around it, in the order of the particle
+    /// set.
+    ///
+    /// Shear rates are only evaluated if a phase is not Newtonian, from the
+    /// velocity gradients weighted by the densities of the particle set, so
+    /// those must be up to date.
+    ///
+    pub fn viscosities(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        let shear_rates = match self.phases.iter().all(|phase| phase.rheology.is_newtonian())
+        {
+            true => vec![0.0; particles.len()],
+            false => UniformField::new(self.kernel.clone(), particles)
+                .velocity_gradients()
+                .iter()
+                .map(shear_rate)
+                .collect(),
+        };
+
+        itertools::izip!(self.phases_of(particles), &particles.temperatures, shear_rates)
+            .map(|(phase, temperature, shear_rate)| phase.viscosity_at(*temperature, shear_rate))
+            .collect()
+    }
+
+    /// Evaluate the buoyancy of every particle under the Boussinesq
+    /// approximation, `a = -β (T - T₀) g`, in the order of the particle set.
+    ///
+    pub fn buoyancy_accelerations(&self, particles: &ParticleSet<N>) -> Vec<FieldVec<N>>
+    {
+        itertools::izip!(self.phases_of(particles), &particles.temperatures)
+            .map(|(phase, temperature)|
+            {
+                self.gravity * (-phase.expansion * (temperature - phase.reference_temperature))
+            })
+            .collect()
+    }
+
+    /// Evaluate the rate of change of temperature of every particle, from
+    /// diffusion of heat through the fluid and conduction of heat from the
+    /// colliders, in the order of the particle set.
+    ///
+    /// Rates are evaluated from the densities of the particle set, so those
+    /// must be up to date.
+    ///
+    pub fn heating_rates(&self, particles: &ParticleSet<N>) -> Vec<f64>
+    {
+        let field = UniformField::new(self.kernel.clone(), particles);
+
+        let diffusivities = self.phases_of(particles)
+            .map(|phase| phase.diffusivity)
+            .collect::<Vec<_>>();
+
+        let mut rates = itertools::izip!(&diffusivities, field.sample(&particles.temperatures[..]).laplacian())
+            .map(|(diffusivity, laplacian)| diffusivity * laplacian)
+            .collect::<Vec<_>>();
+
+        for collider in &self.colliders
+        {
+            let collider_rates = collider.heating_rates(particles, &self.kernel, &diffusivities);
+
+            for (rate, collider_rate) in itertools::izip!(&mut rates, collider_rates)
+            {
+                *rate += collider_rate;
+            }
+        }
+
+        rates
+    }
+
+    /// Evaluate the acceleration of every particle from the forces within the
+    /// fluid, from the colliders and from buoyancy, in the order of the
+    /// particle set. Gravity itself is left out.
+    ///
+    /// Forces are evaluated from the densities of the particle set, so those
+    /// must be up to date.
+    ///
+    pub fn accelerations(&self, particles: &ParticleSet<N>) -> Vec<FieldVec<N>>
+    {
+        let field = UniformField::new(self.kernel.clone(), particles);
+
+        let pressures = self.pressures(particles);
+        let sound_speeds = self.sound_speeds(particles);
+        let rest_densities = self.rest_densities(particles);
+
+        let mut accelerations = pressure_accelerations(&field, &pressures, &sound_speeds, &self.artificial_viscosity);
+        accumulate(&mut accelerations, viscosity_accelerations(&field, &self.viscosities(particles)));
+        accumulate(&mut accelerations, self.surface_tension.accelerations(&field, &rest_densities));
+        accumulate(&mut accelerations, self.buoyancy_accelerations(particles));
+
+        for collider in &self.colliders
+        {
+            let collider_accelerations = collider.accelerations(
+                particles,
+                &self.kernel,
+                &pressures,
+                &rest_densities,
+                &sound_speeds,
+                &self.artificial_viscosity);
+
+            accumulate(&mut accelerations, collider_accelerations);
+        }
+
+        accelerations
+    }
+
+    /// Evaluate the force the fluid exerts on every boundary particle of every
+    /// collider, in the order of the colliders and their boundary particles.
+    ///
+    /// Forces are evaluated from the densities of the particle set, so those
+    /// must be up to date.
+    ///
+    pub fn collider_forces(&self, particles: &ParticleSet<N>) -> Vec<Vec<FieldVec<N>>>
+    {
+        let pressures = self.pressures(particles);
+        let sound_speeds = self.sound_speeds(particles);
+        let rest_densities = self.rest_densities(particles);
+
+        self.colliders.iter()
+            .map(|collider|
+            {
+                collider.forces(
+                    particles,
+                    &self.kernel,
+                    &pressures,
+                    &rest_densities,
+                    &sound_speeds,
+                    &self.artificial_viscosity)
+            })
+            .collect()
+    }
+
+    /// Integrate the density of every particle over a timestep by the
+    /// continuity equation, from the velocities of the particle set, if the
+    /// solver evolves density by continuity.
+    ///
+    pub fn integrate_densities(&self, particles: &mut ParticleSet<N>, timestep: f64)
+    {
+        let DensityEvolution::Continuity = self.density_evolution else { return };
+
+        let density_rates = self.density_rates(particles);
+
+        // The density is kept above the contribution of the particle itself,
+        // which summation never drops below, so a particle thrown clear of
+        // the fluid does not drift to a vanishing density and an unbounded
+        // volume.
+        //
+        let self_influence = self.kernel.influence(0.0);
+
+        for (density, mass, density_rate) in itertools::izip!(&mut particles.densities, &particles.masses, density_rates)
+        {
+            *density = (*density + density_rate * timestep).max(mass * self_influence);
+        }
+    }
+
+    /// Step the particles forward by a timestep under gravity, with
+    /// semi-implicit Euler integration. Under continuity density the density
+    /// of every particle is integrated alongside its position, from its new
+    /// velocity. Particles are then shifted, if the solver shifts particles.
+    ///
+    /// # Arguments
+    ///
+    /// * `particles` - The particles to step, whose densities are updated.
+    /// * `timestep`  - The length of the step.
+    ///
+    pub fn step(&self, particles: &mut ParticleSet<N>, timestep: f64)
+    {
+        self.update_densities(particles);
+
+        let accelerations = self.accelerations(particles);
+        let heating_rates = self.heating_rates(particles);
+
+        for (velocity, acceleration) in itertools::izip!(&mut particles.velocities, accelerations)
+        {
+            *velocity += ((acceleration + self.gravity) * timestep).map(|v| v as f32);
+        }
+
+        self.integrate_densities(particles, timestep);
+
+        for (position, velocity) in itertools::izip!(&mut particles.positions, &particles.velocities)
+        {
+            *position += *velocity * timestep as f32;
+        }
+
+        self.shift_particles(particles, timestep);
+
+        for (temperature, heating_rate) in itertools::izip!(&mut particles.temperatures, heating_rates)
+        {
+            *temperature += heating_rate * timestep;
+        }
+    }
+}
+
+/// Add the accelerations from one force onto those summed so far.
+///
+fn accumulate<const N: usize>(accelerations: &mut [FieldVec<N>], force: Vec<FieldVec<N>>)
+{
+    for (acceleration, force) in itertools::izip!(accelerations, force)
+    {
+        *acceleration += force;
+    }
+}
@@ -0,0 +1,128 @@
+
+use crate::solver::{EquationOfState, Rheology};
+
+/// Represents the material of one of the immiscible fluids in a simulation.
+///
+/// The viscosity of the fluid falls off with temperature as
+/// `μ(T) = μ exp(-b (T - T₀))`, and under the Boussinesq approximation its
+/// density only changes with temperature through the buoyancy
+/// `a = -β (T - T₀) g`, both about the reference temperature `T₀`.
+///
+/// ## Fields
+///
+/// * `equation_of_state`     - The relation between the density of the fluid
+///   and its pressure, and so its rest density.
+/// * `viscosity`             - The dynamic viscosity of the fluid at the
+///   reference temperature, which its rheology scales with the shear rate.
+/// * `rheology`              - The model of how the viscosity of the fluid
+///   depends on the rate at which it is sheared.
+/// * `colour`                - The red, green and blue components of the
+///   colour the fluid is drawn in, each in `[0, 1]`.
+/// * `diffusivity`           - The thermal diffusivity of the fluid.
+/// * `expansion`             - The coefficient of thermal expansion `β` of the
+///   fluid.
+/// * `viscosity_falloff`     - The rate `b` at which the viscosity of the
+///   fluid falls off with temperature.
+/// * `reference_temperature` - The temperature `T₀` at which the fluid has its
+///   rest density and viscosity.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Phase
+{
+    pub equation_of_state: EquationOfState,
+    pub viscosity: f64,
+    pub rheology: Rheology,
+    pub colour: [f32;3],
+    pub diffusivity: f64,
+    pub expansion: f64,
+    pub viscosity_falloff: f64,
+    pub reference_temperature: f64,
+}
+
+impl Phase
+{
+    /// Create a new inviscid Newtonian white phase, which neither conducts
+    /// heat nor changes with temperature.
+    ///
+    pub fn new(equation_of_state: EquationOfState) -> Self
+    {
+        Self {
+            equation_of_state,
+            viscosity: 0.0,
+            rheology: Rheology::Newtonian,
+            colour: [1.0, 1.0, 1.0],
+            diffusivity: 0.0,
+            expansion: 0.0,
+            viscosity_falloff: 0.0,
+            reference_temperature: 0.0,
+        }
+    }
+
+    /// Set the dynamic viscosity of the fluid.
+    ///
+    pub fn with_viscosity(mut self, viscosity: f64) -> Self
+    {
+        self.viscosity = viscosity;
+        self
+    }
+
+    /// Set the model of how the viscosity of the fluid depends on the rate at
+    /// which it is sheared.
+    ///
+    pub fn with_rheology(mut self, rheology: Rheology) -> Self
+    {
+        self.rheology = rheology;
+        self
+    }
+
+    /// Set the colour the fluid is drawn in.
+    ///
+    pub fn with_colour(mut self, colour: [f32;3]) -> Self
+    {
+        self.colour = colour;
+        self
+    }
+
+    /// Set the thermal diffusivity of the fluid.
+    ///
+    pub fn with_diffusivity(mut self, diffusivity: f64) -> Self
+    {
+        self.diffusivity = diffusivity;
+        self
+    }
+
+    /// Set the coefficient of thermal expansion of the fluid, and the
+    /// reference temperature at which it has its rest density.
+    ///
+    pub fn with_expansion(mut self, expansion: f64, reference_temperature: f64) -> Self
+    {
+        self.expansion = expansion;
+        self.reference_temperature = reference_temperature;
+        self
+    }
+
+    /// Set the rate at which the viscosity of the fluid falls off with
+    /// temperature.
+    ///
+    pub fn with_viscosity_falloff(mut self, viscosity_falloff: f64) -> Self
+    {
+        self.viscosity_falloff = viscosity_falloff;
+        self
+    }
+
+    /// Return the density at which the fluid is at zero pressure.
+    ///
+    pub fn rest_density(&self) -> f64
+    {
+        self.equation_of_state.rest_density()
+    }
+
+    /// Return the dynamic viscosity of the fluid at a temperature and shear
+    /// rate.
+    ///
+    pub fn viscosity_at(&self, temperature: f64, shear_rate: f64) -> f64
+    {
+        self.rheology.viscosity(self.viscosity, shear_rate)
+            * (-self.viscosity_falloff * (temperature - self.reference_temperature)).exp()
+    }
+}
@@ -0,0 +1,83 @@
+
+use crate::{Kernel, UniformField};
+use crate::solver::ArtificialViscosity;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// Evaluate the acceleration of every particle down the pressure gradient, in
+/// the order of the particle set.
+///
+/// Uses the multiphase form of Hu and Adams (2006),
+/// `a_i = -1/m_i Σ (p_i V_i² + p_j V_j²) ∇W_ij` with the volumes `V = m / ρ`,
+/// so the force between every pair of particles is equal and opposite, even
+/// across the interface between two fluids. For particles of equal mass this
+/// is the usual `a_i = -Σ m_j (p_i/ρ_i² + p_j/ρ_j²) ∇W_ij`.
+///
+/// The viscous pressure `Π_ij` of an artificial viscosity adds
+/// `-1/m_i Σ m_i m_j Π_ij ∇W_ij` between approaching particles, which is
+/// also equal and opposite.
+///
+/// # Arguments
+///
+/// * `field`                - The field of the particles, with up to date
+///   densities.
+/// * `pressures`            - The pressure at every particle.
+/// * `sound_speeds`         - The speed of sound at every particle.
+/// * `artificial_viscosity` - The artificial viscosity between particles.
+///
+pub fn pressure_accelerations<const N: usize, K: ?Sized + Kernel>(
+    field: &UniformField<'_,N,K>,
+    pressures: &[f64],
+    sound_speeds: &[f64],
+    artificial_viscosity: &ArtificialViscosity,
+) -> Vec<FieldVec<N>>
+{
+    let particles = field.particles();
+    let support = field.kernel().support_radius();
+    let switches = artificial_viscosity.switches(field, sound_speeds);
+
+    maybe_par_iter!(0..particles.len())
+        .map(|index|
+        {
+            let volume = particles.masses[index] / particles.densities[index];
+            let pressure_term = pressures[index] * volume.powi(2);
+            let velocity = particles.velocities[index].map(f64::from);
+
+            let force = field.neighbours_within(&particles.positions[index])
+                .map(|(other, offset)|
+                {
+                    let volume_other = particles.masses[other] / particles.densities[other];
+                    let other_term = pressures[other] * volume_other.powi(2);
+
+                    let viscous_term = match artificial_viscosity.is_none()
+                    {
+                        true => 0.0,
+                        false =>
+                        {
+                            let relative_velocity = velocity - particles.velocities[other].map(f64::from);
+                            let sound_speed = (sound_speeds[index] + sound_speeds[other]) / 2.0;
+                            let density = (particles.densities[index] + particles.densities[other]) / 2.0;
+                            let switch = (switches[index] + switches[other]) / 2.0;
+
+                            let viscous_pressure = artificial_viscosity.viscous_pressure(
+                                support,
+                                &offset,
+                                &relative_velocity,
+                                sound_speed,
+                                density);
+
+                            particles.masses[index] * particles.masses[other] * viscous_pressure * switch
+                        },
+                    };
+
+                    field.influence_gradient(&offset) * -(pressure_term + other_term + viscous_term)
+                })
+                .sum::<FieldVec<N>>();
+
+            force / particles.masses[index]
+        })
+        .collect()
+}
@@ -0,0 +1,110 @@
+
+/// The model of how the viscosity of a fluid depends on the rate at which it
+/// is sheared.
+///
+/// Every model scales a reference viscosity `μ`, the viscosity of the phase:
+/// the consistency of a power law fluid, the viscosity at rest of a Cross or
+/// Carreau fluid, and the plastic viscosity of a Bingham plastic.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Rheology
+{
+    /// A viscosity independent of the shear rate, like water.
+    #[default] Newtonian,
+    /// The power law `μ(γ̇) = μ γ̇^(n-1)`, shear thinning below an index `n`
+    /// of one and shear thickening above it, limited to a greatest viscosity
+    /// so a shear thinning fluid at rest does not become infinitely viscous.
+    PowerLaw { index: f64, max_viscosity: f64 },
+    /// The Cross model `μ(γ̇) = μ∞ + (μ - μ∞) / (1 + (λ γ̇)^n)`, which thins
+    /// from the viscosity at rest down to the viscosity `μ∞` at high shear,
+    /// with the time constant `λ`.
+    Cross { infinite_viscosity: f64, time_constant: f64, index: f64 },
+    /// The Carreau model `μ(γ̇) = μ∞ + (μ - μ∞) (1 + (λ γ̇)²)^((n-1)/2)`, which
+    /// thins like a power law of index `n` beyond the shear rate `1 / λ`, such
+    /// as paint.
+    Carreau { infinite_viscosity: f64, time_constant: f64, index: f64 },
+    /// A Bingham plastic `μ(γ̇) = μ + τ_y (1 - exp(-m γ̇)) / γ̇`, which barely
+    /// flows below the yield stress `τ_y`, such as mud, regularised after
+    /// Papanastasiou (1987) with the exponent `m` so the viscosity at rest is
+    /// the finite `μ + τ_y m`.
+    Bingham { yield_stress: f64, regularisation: f64 },
+}
+
+impl Rheology
+{
+    /// Return whether the viscosity depends on the shear rate.
+    ///
+    pub fn is_newtonian(&self) -> bool
+    {
+        matches!(self, Rheology::Newtonian)
+    }
+
+    /// Return the viscosity of the fluid at a shear rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `viscosity`  - The reference viscosity the model scales.
+    /// * `shear_rate` - The rate at which the fluid is sheared.
+    ///
+    pub fn viscosity(&self, viscosity: f64, shear_rate: f64) -> f64
+    {
+        match *self
+        {
+            Rheology::Newtonian => viscosity,
+            // Only a shear thinning fluid becomes infinitely viscous at rest,
+            // and a shear thickening one loses all its viscosity instead.
+            //
+            Rheology::PowerLaw { index, max_viscosity } => match shear_rate
+            {
+                0.0 if index < 1.0 => max_viscosity,
+                shear_rate => (viscosity * shear_rate.powf(index - 1.0)).min(max_viscosity),
+            },
+            Rheology::Cross { infinite_viscosity, time_constant, index } =>
+                infinite_viscosity + (viscosity - infinite_viscosity)
+                    / (1.0 + (time_constant * shear_rate).powf(index)),
+            Rheology::Carreau { infinite_viscosity, time_constant, index } =>
+                infinite_viscosity + (viscosity - infinite_viscosity)
+                    * (1.0 + (time_constant * shear_rate).powi(2)).powf((index - 1.0) / 2.0),
+            Rheology::Bingham { yield_stress, regularisation } => match shear_rate
+            {
+                0.0 => viscosity + yield_stress * regularisation,
+                shear_rate => viscosity + yield_stress * (-(-regularisation * shear_rate).exp_m1()) / shear_rate,
+            },
+        }
+    }
+}
+
+/// Return the shear rate `γ̇ = √(2 D:D)` of a velocity gradient, from its
+/// rate of strain tensor `D = (∇v + ∇vᵀ) / 2`.
+///
+pub fn shear_rate<const N: usize>(velocity_gradient: &nalgebra::SMatrix<f64,N,N>) -> f64
+{
+    let strain_rate = (velocity_gradient + velocity_gradient.transpose()) / 2.0;
+    (2.0 * strain_rate.norm_squared()).sqrt()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn power_law_at_rest()
+    {
+        let power_law = |index| Rheology::PowerLaw { index, max_viscosity: 100.0 };
+
+        assert_eq!(power_law(0.5).viscosity(2.0, 0.0), 100.0);
+        assert_eq!(power_law(1.0).viscosity(2.0, 0.0), 2.0);
+        assert_eq!(power_law(1.5).viscosity(2.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn power_law_thickens_from_rest()
+    {
+        let power_law = Rheology::PowerLaw { index: 1.5, max_viscosity: 100.0 };
+
+        assert!(power_law.viscosity(2.0, 1e-6) < 1e-2);
+        assert_eq!(power_law.viscosity(2.0, 4.0), 4.0);
+        assert_eq!(power_law.viscosity(2.0, 1e6), 100.0);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::{FieldKernel, Kernel};
+use crate::solver::{sample_edge, Collider};
+
+type FieldPos<const N: usize> = nalgebra::SVector<f32,N>;
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// The shape of a rigid body, about its centre of mass and unrotated.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape
+{
+    /// A circle of a radius.
+    Circle { radius: f64 },
+    /// A convex polygon, with its vertices in order around it.
+    Polygon { vertices: Vec<FieldVec<2>> },
+}
+
+/// Represents a solid in two dimensions that moves with the fluid, pushed by
+/// its pressure and pushing back on it, such as a floating or sinking object.
+///
+/// The body is sampled as a layer of boundary particles over its surface,
+/// which are placed as a [`Collider`] moving with the body every step. The
+/// fluid pushes on the boundary particles with the reaction to the forces
+/// they exert on it, and the body is integrated from the resulting force and
+/// torque, with its own mass, moment of inertia and gravity.
+///
+/// A body also comes into contact with colliders, such as the floor of a
+/// tank, without friction or bounce. Bodies only feel one another through
+/// the fluid between them.
+///
+/// ## Fields
+///
+/// * `shape`            - The shape of the body.
+/// * `position`         - The position of the centre of mass of the body.
+/// * `angle`            - The angle the body is rotated by, anticlockwise.
+/// * `velocity`         - The velocity of the centre of mass of the body.
+/// * `angular_velocity` - The rate of rotation of the body, anticlockwise.
+/// * `mass`             - The mass of the body.
+/// * `inertia`          - The moment of inertia of the body about its centre
+///   of mass.
+/// * `boundary`         - The position of every boundary particle, about the
+///   centre of mass and unrotated.
+/// * `spacing`          - The greatest spacing of the boundary particles.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct RigidBody
+{
+    pub shape: Shape,
+    pub position: FieldVec<2>,
+    pub angle: f64,
+    pub velocity: FieldVec<2>,
+    pub angular_velocity: f64,
+    pub mass: f64,
+    pub inertia: f64,
+    boundary: Vec<FieldVec<2>>,
+    spacing: f64,
+}
+
+impl RigidBody
+{
+    /// Create a new rigid body of a circle at rest, with boundary particles
+    /// spaced evenly around its circumference.
+    ///
+    /// # Arguments
+    ///
+    /// * `centre`  - The centre of the circle.
+    /// * `radius`  - The radius of the circle.
+    /// * `density` - The density of the body, its mass per unit area.
+    /// * `spacing` - The greatest spacing of the boundary particles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the body has no mass, as with a radius or density that is
+    /// not positive.
+    ///
+    pub fn circle(centre: FieldVec<2>, radius: f64, density: f64, spacing: f64) -> Self
+    {
+        let count = (std::f64::consts::TAU * radius / spacing).ceil().max(3.0) as usize;
+
+        let boundary = (0..count)
+            .map(|index|
+            {
+                let angle = std::f64::consts::TAU * index as f64 / count as f64;
+                FieldVec::<2>::new(angle.cos(), angle.sin()) * radius
+            })
+            .collect();
+
+        let mass = density * std::f64::consts::PI * radius.powi(2);
+        let inertia = mass * radius.powi(2) / 2.0;
+
+        assert!(mass > 0.0 && inertia > 0.0, "rigid body has no mass");
+
+        Self {
+            shape: Shape::Circle { radius },
+            position: centre,
+            angle: 0.0,
+            velocity: FieldVec::zeros(),
+            angular_velocity: 0.0,
+            mass,
+            inertia,
+            boundary,
+            spacing,
+        }
+    }
+
+    /// Create a new rigid body of a convex polygon at rest, with boundary
+    /// particles spaced evenly along every edge. The body is placed with its
+    /// centre of mass at the centroid of the polygon.
+    ///
+    /// # Arguments
+    ///
+    /// * `vertices` - The vertices of the polygon, in order around it.
+    /// * `density`  - The density of the body, its mass per unit area.
+    /// * `spacing`  - The greatest spacing of the boundary particles.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polygon has no area, or the body has no mass, as with a
+    /// density that is not positive.
+    ///
+    pub fn polygon(vertices: &[FieldVec<2>], density: f64, spacing: f64) -> Self
+    {
+        // The area, centroid and second moment of area about the origin, summed
+        // over the triangles between the origin and every edge.
+        //
+        let (area, moment, second_moment) = vertices.iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(start, end)|
+            {
+                let cross = start.perp(end);
+                let moment = (start + end) * (cross / 6.0);
+                let second_moment = cross * (start.dot(start) + start.dot(end) + end.dot(end)) / 12.0;
+
+                (cross / 2.0, moment, second_moment)
+            })
+            .fold((0.0, FieldVec::zeros(), 0.0), |(area, moment, second_moment), (a, m, s)|
+            {
+                (area + a, moment + m, second_moment + s)
+            });
+
+        assert!(area != 0.0, "polygon has no area");
+
+        let centroid = moment / area;
+        let mass = density * area.abs();
+        let inertia = density * second_moment.abs() - mass * centroid.norm_squared();
+
+        assert!(mass > 0.0 && inertia > 0.0, "rigid body has no mass");
+
+        let vertices = vertices.iter()
+            .map(|vertex| vertex - centroid)
+            .collect::<Vec<_>>();
+
+        let boundary = vertices.iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .flat_map(|(start, end)| sample_edge(start.map(|v| v as f32), end.map(|v| v as f32), spacing as f32))
+            .map(|position| position.map(f64::from))
+            .collect();
+
+        Self {
+            shape: Shape::Polygon { vertices },
+            position: centroid,
+            angle: 0.0,
+            velocity: FieldVec::zeros(),
+            angular_velocity: 0.0,
+            mass,
+            inertia,
+            boundary,
+            spacing,
+        }
+    }
+
+    /// Return the offset of every boundary particle from the centre of mass,
+    /// rotated with the body.
+    ///
+    pub fn boundary_offsets(&self) -> Vec<FieldVec<2>>
+    {
+        let rotation = nalgebra::Rotation2::new(self.angle);
+
+        self.boundary.iter()
+            .map(|offset| rotation * offset)
+            .collect()
+    }
+
+    /// Return the position of every boundary particle.
+    ///
+    pub fn boundary_positions(&self) -> Vec<FieldPos<2>>
+    {
+        self.boundary_offsets().iter()
+            .map(|offset| (self.position + offset).map(|v| v as f32))
+            .collect()
+    }
+
+    /// Return the velocity of every boundary particle, `v + ω × r`.
+    ///
+    pub fn boundary_velocities(&self) -> Vec<FieldVec<2>>
+    {
+        self.boundary_offsets().iter()
+            .map(|offset| self.velocity + FieldVec::<2>::new(-offset.y, offset.x) * self.angular_velocity)
+            .collect()
+    }
+
+    /// Create the collider of the body where it is now, with its boundary
+    /// particles moving with it.
+    ///
+    pub fn collider<K: ?Sized + Kernel>(&self, kernel: &FieldKernel<2,K>) -> Collider<2>
+    {
+        Collider::new(self.boundary_positions(), kernel)
+            .with_velocities(self.boundary_velocities())
+    }
+
+    /// Accelerate the body over a timestep by the forces on its boundary
+    /// particles and gravity.
+    ///
+    /// # Arguments
+    ///
+    /// * `forces`   - The force on every boundary particle, in the order of
+    ///   the boundary particles.
+    /// * `gravity`  - The acceleration due to gravity.
+    /// * `timestep` - The length of the step.
+    ///
+    pub fn accelerate(&mut self, forces: &[FieldVec<2>], gravity: FieldVec<2>, timestep: f64)
+    {
+        let (force, torque) = itertools::izip!(self.boundary_offsets(), forces)
+            .fold((FieldVec::zeros(), 0.0), |(force, torque), (offset, boundary_force)|
+            {
+                (force + boundary_force, torque + offset.perp(boundary_force))
+            });
+
+        self.velocity += (force / self.mass + gravity) * timestep;
+        self.angular_velocity += torque / self.inertia * timestep;
+    }
+
+    /// Move and rotate the body over a timestep with its velocities.
+    ///
+    pub fn advance(&mut self, timestep: f64)
+    {
+        self.position += self.velocity * timestep;
+        self.angle += self.angular_velocity * timestep;
+    }
+
+    /// Resolve the contact of the body with a collider, where boundary
+    /// particles of the body come closer than their spacing to those of the
+    /// collider.
+    ///
+    /// The body is pushed back out along the mean normal of the contact, by
+    /// its greatest depth, and the velocity of the body into the collider at
+    /// the mean point of contact is removed by an impulse, which also turns
+    /// the body if it lands off centre.
+    ///
+    pub fn collide(&mut self, collider: &Collider<2>)
+    {
+        // The depth, normal and offset from the centre of mass of every
+        // contact, with the normal pointing out of the collider.
+        //
+        let spacing = self.spacing;
+
+        let contacts = self.boundary_offsets().into_iter()
+            .flat_map(|offset|
+            {
+                let position = (self.position + offset).map(|v| v as f32);
+
+                collider.neighbours_within(&position, spacing)
+                    .filter(|(_boundary, separation)| separation.norm() > 0.0)
+                    .map(move |(_boundary, separation)|
+                    {
+                        (spacing - separation.norm(), -separation.normalize(), offset)
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let Some(depth) = contacts.iter().map(|(depth, _normal, _offset)| *depth).reduce(f64::max) else { return };
+
+        let total_depth = contacts.iter().map(|(depth, _normal, _offset)| depth).sum::<f64>();
+        let normal = contacts.iter().map(|(depth, normal, _offset)| normal * *depth).sum::<FieldVec<2>>();
+        let offset = contacts.iter().map(|(depth, _normal, offset)| offset * *depth).sum::<FieldVec<2>>() / total_depth;
+
+        let Some(normal) = normal.try_normalize(0.0) else { return };
+
+        self.position += normal * depth;
+
+        let contact_velocity = self.velocity + FieldVec::<2>::new(-offset.y, offset.x) * self.angular_velocity;
+        let approach_speed = contact_velocity.dot(&normal);
+        if approach_speed >= 0.0 { return };
+
+        let arm = offset.perp(&normal);
+        let impulse = -approach_speed / (1.0 / self.mass + arm.powi(2) / self.inertia);
+
+        self.velocity += normal * (impulse / self.mass);
+        self.angular_velocity += arm * impulse / self.inertia;
+    }
+}
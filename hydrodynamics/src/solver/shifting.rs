@@ -0,0 +1,76 @@
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// The Fickian particle shifting of Lind et al. (2012), which moves every
+/// particle a little down the gradient of the concentration of particles, the
+/// kernel sum `C`, to even out clusters and voids.
+///
+/// Every particle is shifted by `δr_i = -A h |v_i| Δt ∇C_i` after every step,
+/// with the strength `A` and the radius of support `h`, in the form of
+/// Skillen et al. (2013) which scales with the distance the particle moves.
+/// On the free surface only the tangential part of the shift is kept, so
+/// particles are not pulled out of the fluid.
+///
+/// ## Fields
+///
+/// * `strength` - The strength `A` of the shifting, zero for none.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParticleShifting
+{
+    pub strength: f64,
+}
+
+impl ParticleShifting
+{
+    /// Create a new particle shifting of a strength, typically between one
+    /// and six.
+    ///
+    pub fn new(strength: f64) -> Self
+    {
+        Self { strength }
+    }
+
+    /// Return whether the shifting moves no particles.
+    ///
+    pub fn is_none(&self) -> bool
+    {
+        self.strength == 0.0
+    }
+
+    /// Evaluate the shift of every particle, in the order of the particle
+    /// set.
+    ///
+    /// # Arguments
+    ///
+    /// * `support`                 - The radius of support of the kernel.
+    /// * `speeds`                  - The speed of every particle.
+    /// * `concentration_gradients` - The gradient of the kernel sum at every
+    ///   particle.
+    /// * `surface_normals`         - The outward normal of every particle on
+    ///   the free surface.
+    /// * `timestep`                - The length of the step.
+    ///
+    pub fn shifts<const N: usize>(
+        &self,
+        support: f64,
+        speeds: &[f64],
+        concentration_gradients: &[FieldVec<N>],
+        surface_normals: &[Option<FieldVec<N>>],
+        timestep: f64,
+    ) -> Vec<FieldVec<N>>
+    {
+        itertools::izip!(speeds, concentration_gradients, surface_normals)
+            .map(|(speed, gradient, normal)|
+            {
+                let shift = gradient * (-self.strength * support * speed * timestep);
+
+                match normal
+                {
+                    Some(normal) => shift - normal * shift.dot(normal),
+                    None => shift,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,175 @@
+
+use std::f64::consts::PI;
+
+use crate::{Kernel, UniformField};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// The model of surface tension between a fluid and empty space.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SurfaceTension
+{
+    /// No surface tension.
+    #[default] None,
+    /// The continuum surface force of a colour field, `f = σ κ ∇c`, where the
+    /// curvature `κ = -∇·(∇c / |∇c|)` is only evaluated where the gradient of
+    /// the colour field `|∇c| h` exceeds a threshold.
+    ColourField { coefficient: f64, threshold: f64 },
+    /// The cohesion and curvature forces of Akinci et al. (2013), which pull
+    /// neighbouring particles together and flatten the surface.
+    Akinci { coefficient: f64 },
+}
+
+impl SurfaceTension
+{
+    /// Evaluate the acceleration of every particle due to surface tension, in
+    /// the order of the particle set.
+    ///
+    /// # Arguments
+    ///
+    /// * `field`          - The field of the particles, with up to date densities.
+    /// * `rest_densities` - The density at rest of the fluid of every particle.
+    ///
+    pub fn accelerations<const N: usize, K: ?Sized + Kernel>(
+        &self,
+        field: &UniformField<'_,N,K>,
+        rest_densities: &[f64],
+    ) -> Vec<FieldVec<N>>
+    {
+        match *self
+        {
+            SurfaceTension::None => vec![FieldVec::zeros(); field.particles().len()],
+            SurfaceTension::ColourField { coefficient, threshold } =>
+                colour_field_accelerations(field, coefficient, threshold),
+            SurfaceTension::Akinci { coefficient } =>
+                akinci_accelerations(field, coefficient, rest_densities),
+        }
+    }
+}
+
+/// Evaluate the continuum surface force of a colour field, one everywhere in
+/// the fluid, on every particle.
+///
+fn colour_field_accelerations<const N: usize, K: ?Sized + Kernel>(
+    field: &UniformField<'_,N,K>,
+    coefficient: f64,
+    threshold: f64,
+) -> Vec<FieldVec<N>>
+{
+    let particles = field.particles();
+    let support = field.kernel().support_radius();
+
+    // The gradient of the colour field points into the fluid, and is only
+    // non-zero near the surface.
+    //
+    let colours = field.sample(vec![1.0; particles.len()]);
+    let normals = colours.summation_gradient().gradients().iter()
+        .map(|normal| FieldVec::<N>::from(*normal))
+        .collect::<Vec<_>>();
+
+    let unit_normals = normals.iter()
+        .map(|normal| match normal.norm() * support > threshold
+        {
+            true => normal.normalize(),
+            false => FieldVec::zeros(),
+        })
+        .collect::<Vec<_>>();
+
+    maybe_par_iter!(0..particles.len())
+        .map(|index|
+        {
+            if unit_normals[index].norm() == 0.0 { return FieldVec::zeros() };
+
+            // The curvature, as the negative divergence of the unit normals.
+            //
+            let curvature = -field.neighbours_within(&particles.positions[index])
+                .map(|(other, offset)|
+                {
+                    let volume = particles.masses[other] / particles.densities[other];
+                    volume * (unit_normals[other] - unit_normals[index]).dot(&field.influence_gradient(&offset))
+                })
+                .sum::<f64>();
+
+            normals[index] * (coefficient * curvature / particles.densities[index])
+        })
+        .collect()
+}
+
+/// Evaluate the cohesion and curvature forces of Akinci et al. on every
+/// particle.
+///
+/// Both forces are scaled by `K_ij = (ρ₀_i + ρ₀_j) / (ρ_i + ρ_j)`, which
+/// strengthens them where particles near the surface lack neighbours.
+///
+fn akinci_accelerations<const N: usize, K: ?Sized + Kernel>(
+    field: &UniformField<'_,N,K>,
+    coefficient: f64,
+    rest_densities: &[f64],
+) -> Vec<FieldVec<N>>
+{
+    let particles = field.particles();
+    let support = field.kernel().support_radius();
+
+    // The normals, scaled by the radius of support and pointing out of the
+    // fluid, so they are dimensionless and only non-zero near the surface.
+    //
+    let normals = maybe_par_iter!(0..particles.len())
+        .map(|index|
+        {
+            field.neighbours_within(&particles.positions[index])
+                .map(|(other, offset)|
+                {
+                    let volume = particles.masses[other] / particles.densities[other];
+                    field.influence_gradient(&offset) * (-support * volume)
+                })
+                .sum::<FieldVec<N>>()
+        })
+        .collect::<Vec<_>>();
+
+    maybe_par_iter!(0..particles.len())
+        .map(|index|
+        {
+            field.neighbours_within(&particles.positions[index])
+                .filter(|(other, _offset)| *other != index)
+                .map(|(other, offset)|
+                {
+                    let radius = offset.norm();
+                    let correction = (rest_densities[index] + rest_densities[other])
+                        / (particles.densities[index] + particles.densities[other]);
+
+                    let cohesion = match radius > 0.0
+                    {
+                        true => offset * (particles.masses[other] * cohesion(support, radius) / radius),
+                        false => FieldVec::zeros(),
+                    };
+                    let curvature = normals[other] - normals[index];
+
+                    (cohesion + curvature) * (coefficient * correction)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// The cohesion spline of Akinci et al., attractive beyond half the radius of
+/// support `h` and repulsive within it.
+///
+/// The coefficient is that of three dimensions in every dimension, and is
+/// absorbed into the coefficient of surface tension.
+///
+fn cohesion(h: f64, r: f64) -> f64
+{
+    let coefficient = 32.0 / (PI * h.powi(9));
+    let shape = (h - r).powi(3) * r.powi(3);
+
+    match r
+    {
+        r if r > h => 0.0,
+        r if 2.0 * r > h => coefficient * shape,
+        _ => coefficient * (2.0 * shape - h.powi(6) / 64.0),
+    }
+}
@@ -0,0 +1,63 @@
+
+use crate::{Kernel, UniformField};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type FieldVec<const N: usize> = nalgebra::SVector<f64,N>;
+
+/// Evaluate the acceleration of every particle due to the viscosity of the
+/// fluid, in the order of the particle set.
+///
+/// Uses the multiphase form of Hu and Adams (2006),
+/// `a_i = 1/m_i Σ μ_ij (V_i² + V_j²) W'(r) v_ij r / (r² + ε h²)`, with the
+/// harmonic mean `μ_ij = 2μ_i μ_j / (μ_i + μ_j)` of the viscosities and the
+/// volumes `V = m / ρ`, so the force between every pair of particles is equal
+/// and opposite, even across the interface between two fluids.
+///
+/// # Arguments
+///
+/// * `field`       - The field of the particles, with up to date densities.
+/// * `viscosities` - The dynamic viscosity at every particle.
+///
+pub fn viscosity_accelerations<const N: usize, K: ?Sized + Kernel>(
+    field: &UniformField<'_,N,K>,
+    viscosities: &[f64],
+) -> Vec<FieldVec<N>>
+{
+    let particles = field.particles();
+    let support = field.kernel().support_radius();
+
+    // The smoothing of the distance between particles, which keeps the force
+    // between particles that nearly coincide finite.
+    //
+    let epsilon = 0.01 * support.powi(2);
+
+    maybe_par_iter!(0..particles.len())
+        .map(|index|
+        {
+            let volume = particles.masses[index] / particles.densities[index];
+            let velocity = particles.velocities[index].map(f64::from);
+
+            let force = field.neighbours_within(&particles.positions[index])
+                .map(|(other, offset)|
+                {
+                    let viscosity_sum = viscosities[index] + viscosities[other];
+                    if viscosity_sum == 0.0 { return FieldVec::zeros() };
+
+                    let radius = offset.norm();
+                    let viscosity = 2.0 * viscosities[index] * viscosities[other] / viscosity_sum;
+                    let volume_other = particles.masses[other] / particles.densities[other];
+                    let relative_velocity = velocity - particles.velocities[other].map(f64::from);
+
+                    let derivative = field.kernel().influence_derivative(radius);
+                    let weight = viscosity * (volume.powi(2) + volume_other.powi(2)) * derivative * radius / (radius.powi(2) + epsilon);
+
+                    relative_velocity * weight
+                })
+                .sum::<FieldVec<N>>();
+
+            force / particles.masses[index]
+        })
+        .collect()
+}
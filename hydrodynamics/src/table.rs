@@ -0,0 +1,219 @@
+
+use crate::Kernel;
+
+/// The interpolation between the samples of a kernel table.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation
+{
+    /// Linear interpolation between the two nearest samples.
+    #[default] Linear,
+    /// Catmull-Rom cubic interpolation between the four nearest samples.
+    Cubic,
+}
+
+/// Represents a normalised smoothing kernel, and its derivative, tabulated at
+/// evenly spaced values of `r²` over the radius of support.
+///
+/// Sampling by `r²` rather than `r` places more samples towards the edge of
+/// the support, where most neighbours lie, and avoids a square root when the
+/// squared distance is already known. The derivative is tabulated divided by
+/// `r`, so the table can be looked up by `r²` alone.
+///
+/// Only kernels that are polynomials in `r²`, such as [`Poly6`], are smooth in
+/// `r²`, and cubic interpolation reproduces them nearly exactly. Kernels with
+/// odd powers of `r`, such as the splines and the Wendland kernels, vary with
+/// `√(r²)` near `r = 0`, where the error of either interpolation is largest,
+/// so cubic interpolation gains little over linear for their derivatives.
+///
+/// [`Poly6`]: crate::kernels::Poly6
+///
+/// ## Fields
+///
+/// * `support`       - The radius of support for the smoothing kernel.
+/// * `interpolation` - The interpolation between samples.
+/// * `values`        - The normalised kernel at every sample.
+/// * `derivatives`   - The derivative of the normalised kernel with respect
+///   to `r`, divided by `r`, at every sample.
+///
+#[derive(Clone, Debug)]
+pub struct KernelTable
+{
+    support: f64,
+    interpolation: Interpolation,
+    values: Vec<f64>,
+    derivatives: Vec<f64>,
+}
+
+impl KernelTable
+{
+    /// Tabulate a kernel over its radius of support.
+    ///
+    /// # Arguments
+    ///
+    /// * `kernel`        - The smoothing kernel to tabulate.
+    /// * `support`       - The radius of support for the smoothing kernel.
+    /// * `normal`        - The normalisation coefficient of the kernel.
+    /// * `samples`       - The number of samples, spanning `0 <= r² <= h²`.
+    /// * `interpolation` - The interpolation between samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than two samples.
+    ///
+    pub fn new<K>(kernel: &K, support: f64, normal: f64, samples: usize, interpolation: Interpolation) -> Self
+    where
+        K: ?Sized + Kernel,
+    {
+        assert!(samples >= 2, "a kernel table needs at least two samples");
+
+        let radius = |i: usize| (i as f64 / (samples - 1) as f64).sqrt() * support;
+
+        let values = (0..samples)
+            .map(|i| kernel.kernel(support, radius(i)) * normal)
+            .collect();
+
+        let mut derivatives = (0..samples)
+            .map(|i| kernel.derivative(support, radius(i)) / radius(i) * normal)
+            .collect::<Vec<_>>();
+
+        // The derivative divided by `r` is undefined at `r = 0`, so extrapolate
+        // it from the neighbouring samples.
+        //
+        derivatives[0] = match samples
+        {
+            2 => derivatives[1],
+            3 => 2.0 * derivatives[1] - derivatives[2],
+            _ => 3.0 * derivatives[1] - 3.0 * derivatives[2] + derivatives[3],
+        };
+
+        Self {
+            support,
+            interpolation,
+            values,
+            derivatives,
+        }
+    }
+
+    /// Return the number of samples in the table.
+    ///
+    pub fn samples(&self) -> usize
+    {
+        self.values.len()
+    }
+
+    /// Return the interpolation between samples.
+    ///
+    pub fn interpolation(&self) -> Interpolation
+    {
+        self.interpolation
+    }
+
+    /// Interpolate the normalised kernel at a distance `r`.
+    ///
+    pub fn value(&self, r: f64) -> f64
+    {
+        self.lookup(&self.values, r)
+    }
+
+    /// Interpolate the derivative of the normalised kernel at a distance `r`.
+    ///
+    pub fn derivative(&self, r: f64) -> f64
+    {
+        self.lookup(&self.derivatives, r) * r
+    }
+
+    fn lookup(&self, samples: &[f64], r: f64) -> f64
+    {
+        if r > self.support { return 0.0 };
+
+        let last = samples.len() - 1;
+        let position = (r * r) / (self.support * self.support) * last as f64;
+        let i = (position.floor() as usize).min(last - 1);
+        let t = position - i as f64;
+
+        match self.interpolation
+        {
+            Interpolation::Linear =>
+            {
+                samples[i] + (samples[i + 1] - samples[i]) * t
+            }
+            Interpolation::Cubic =>
+            {
+                // Extrapolate quadratically beyond either end of the table, so
+                // the first and last intervals are as accurate as the rest, and
+                // linearly when there are only two samples.
+                //
+                let p1 = samples[i];
+                let p2 = samples[i + 1];
+                let before = (i > 0).then(|| samples[i - 1]);
+                let after = (i + 2 <= last).then(|| samples[i + 2]);
+
+                let p0 = match (before, after)
+                {
+                    (Some(p0), _) => p0,
+                    (None, Some(p3)) => 3.0 * p1 - 3.0 * p2 + p3,
+                    (None, None) => 2.0 * p1 - p2,
+                };
+                let p3 = match (before, after)
+                {
+                    (_, Some(p3)) => p3,
+                    (Some(p0), None) => 3.0 * p2 - 3.0 * p1 + p0,
+                    (None, None) => 2.0 * p2 - p1,
+                };
+
+                0.5 * (2.0 * p1
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+                    + (3.0 * (p1 - p2) + p3 - p0) * t * t * t)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::kernels::*;
+
+    /// The greatest error of the value and derivative of a table over the
+    /// support, relative to the greatest magnitude of the exact kernel.
+    ///
+    fn errors(kernel: &dyn Kernel, interpolation: Interpolation) -> (f64, f64)
+    {
+        let table = KernelTable::new(kernel, 1.0, 1.0, 1024, interpolation);
+        let radii = (0..=10000).map(|i| i as f64 / 10000.0).collect::<Vec<_>>();
+
+        let error = |exact: &dyn Fn(f64) -> f64, tabulated: &dyn Fn(f64) -> f64|
+        {
+            let scale = radii.iter().map(|r| exact(*r).abs()).fold(0.0, f64::max);
+            radii.iter().map(|r| (exact(*r) - tabulated(*r)).abs()).fold(0.0, f64::max) / scale
+        };
+
+        (
+            error(&|r| kernel.kernel(1.0, r), &|r| table.value(r)),
+            error(&|r| kernel.derivative(1.0, r), &|r| table.derivative(r)),
+        )
+    }
+
+    #[test]
+    fn poly6_table_is_accurate()
+    {
+        let (value, derivative) = errors(&Poly6, Interpolation::Linear);
+        assert!(value < 1e-6 && derivative < 1e-6, "linear errors {} {}", value, derivative);
+
+        let (value, derivative) = errors(&Poly6, Interpolation::Cubic);
+        assert!(value < 1e-9 && derivative < 1e-9, "cubic errors {} {}", value, derivative);
+    }
+
+    #[test]
+    fn wendland_c2_table_is_accurate()
+    {
+        let (value, derivative) = errors(&WendlandC2, Interpolation::Linear);
+        assert!(value < 1e-4 && derivative < 3e-3, "linear errors {} {}", value, derivative);
+
+        let (value, derivative) = errors(&WendlandC2, Interpolation::Cubic);
+        assert!(value < 4e-5 && derivative < 3e-3, "cubic errors {} {}", value, derivative);
+    }
+}
@@ -6,4 +6,6 @@ pub mod nball;
 
 pub mod euclidean;
 
+pub mod random;
+
 pub mod to_array;
@@ -17,3 +17,44 @@ pub fn volume(n: u8, r: f64) -> f64
     const PI: f64 = std::f64::consts::PI;
     PI.powf(n as f64 / 2.0) * r.powi(n as i32) / fuga::gamma(1.0 + n as f64 / 2.0)
 }
+
+/// Calculate the surface area of an n-ball of dimensionality `n` and radius
+/// `r`, that is the measure of its bounding (n-1)-sphere.
+///
+/// * `n`   - The number of dimensions the n-ball exists in.
+/// * `r`   - The radius of the n-ball.
+///
+/// The surface area is the derivative of the volume with respect to the
+/// radius, `dV/dr = n V(n, r) / r`, so integrating a radial function against
+/// it integrates that function over n-dimensional space. We calculate it
+/// using the closed-form formula, which is also defined at `r = 0`.
+///
+/// https://en.wikipedia.org/wiki/N-sphere
+///
+pub fn surface_area(n: u8, r: f64) -> f64
+{
+    const PI: f64 = std::f64::consts::PI;
+    2.0 * PI.powf(n as f64 / 2.0) * r.powi(n as i32 - 1) / fuga::gamma(n as f64 / 2.0)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn surface_area_is_derivative_of_volume()
+    {
+        let delta = 1e-6;
+
+        for n in 1..=4
+        {
+            for r in [0.5, 1.0, 2.0]
+            {
+                let derivative = (volume(n, r + delta) - volume(n, r - delta)) / (2.0 * delta);
+                assert!((surface_area(n, r) - derivative).abs() < 1e-6 * derivative,
+                    "surface area {} but dV/dr {} for n = {}, r = {}", surface_area(n, r), derivative, n, r);
+            }
+        }
+    }
+}
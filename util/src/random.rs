@@ -0,0 +1,49 @@
+
+/// A small seedable pseudo-random number generator.
+///
+/// Implements the SplitMix64 generator, whose output depends only on the seed,
+/// and so is identical across platforms, releases and runs.
+///
+/// https://prng.di.unimi.it/splitmix64.c
+///
+#[derive(Clone, Debug)]
+pub struct SplitMix64
+{
+    state: u64,
+}
+
+impl SplitMix64
+{
+    /// Create a new generator from a seed.
+    ///
+    pub fn new(seed: u64) -> Self
+    {
+        Self { state: seed }
+    }
+
+    /// Generate the next 64 random bits.
+    ///
+    pub fn next_u64(&mut self) -> u64
+    {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Generate a uniformly distributed number in `[0, 1)`.
+    ///
+    pub fn next_f64(&mut self) -> f64
+    {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Generate a uniformly distributed number in `[0, 1)`.
+    ///
+    pub fn next_f32(&mut self) -> f32
+    {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}